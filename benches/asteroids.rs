@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use aoc::asteroids::{self, Direction};
+
+/// A square field of roughly `count` asteroids filling about half of its cells, always the same
+/// for a given `count`.
+fn field(count: usize) -> Vec<(i32, i32)> {
+    let side = ((count * 2) as f64).sqrt().ceil() as i32;
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut asteroids = Vec::with_capacity(count);
+    for y in 0..side {
+        for x in 0..side {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            if state >> 63 == 1 && asteroids.len() < count {
+                asteroids.push((x, y));
+            }
+        }
+    }
+    asteroids
+}
+
+/// Counts directions by hashing them for every station, for comparison.
+fn hashed_best(asteroids: &[(i32, i32)]) -> usize {
+    asteroids.iter()
+        .map(|&(sx, sy)| {
+            asteroids.iter()
+                .filter(|a| **a != (sx, sy))
+                .map(|(x, y)| Direction::new(x - sx, y - sy))
+                .collect::<HashSet<_>>()
+                .len()
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn time<F: FnOnce() -> usize>(f: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let best = f();
+    (start.elapsed(), best)
+}
+
+/// Times the exact counts on growing fields. Counting is quadratic, so each tenfold field should
+/// take about a hundred times as long, which the last column shows.
+fn main() {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut previous: Option<Duration> = None;
+    for &count in &[1_000, 10_000, 100_000] {
+        let field = field(count);
+        let best = |threads| asteroids::visible_counts(&field, threads).into_iter().max().unwrap_or(0);
+
+        let (single, expected) = time(|| best(1));
+        let (parallel, visible) = time(|| best(threads));
+        assert_eq!(visible, expected);
+        // Hashing every pair takes minutes on the largest field.
+        let hashed = if count <= 10_000 {
+            let (hashed, visible) = time(|| hashed_best(&field));
+            assert_eq!(visible, expected);
+            format!("{:>12?}", hashed)
+        } else {
+            format!("{:>12}", "-")
+        };
+
+        let growth = previous.map_or(String::from("-"), |previous| {
+            format!("x{:.0}", single.as_secs_f64() / previous.as_secs_f64())
+        });
+        previous = Some(single);
+
+        println!(
+            "{:>7} asteroids: hashed {}  table {:>12?}  table x{} {:>12?}  growth {:>5}  (best sees {})",
+            count, hashed, single, threads, parallel, growth, expected);
+    }
+}
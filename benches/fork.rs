@@ -0,0 +1,42 @@
+use std::time::{Duration, Instant};
+use aoc::computer::Computer;
+
+const ITERATIONS: u32 = 1000;
+
+fn time<F: FnMut()>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    for &size in &[4_096, 65_536, 1_048_576] {
+        let memory: Vec<i64> = (0..size).collect();
+
+        let vec_clone = time(|| {
+            let copy = memory.clone();
+            assert_eq!(copy.len(), size as usize);
+        });
+
+        // Writes a single cell and halts, so each run of a fork dirties exactly one page.
+        let mut program = memory.clone();
+        program[..5].copy_from_slice(&[1101, 1, 1, 7, 99]);
+        let computer: Computer = Computer::load(program);
+
+        let fork = time(|| {
+            let forked = computer.fork();
+            assert_eq!(forked.instructions(), 0);
+        });
+
+        let fork_and_write = time(|| {
+            let mut outputs = computer.fork().outputs(vec![]);
+            assert!(outputs.next().is_none());
+        });
+
+        println!(
+            "{:>9} words: Vec::clone {:>12?}  fork {:>12?}  fork+write {:>12?}",
+            size, vec_clone, fork, fork_and_write);
+    }
+}
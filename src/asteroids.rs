@@ -0,0 +1,228 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::thread;
+
+/// Offset tables bigger than this many entries fall back to hashing `Direction`s.
+const MAX_TABLE: usize = 1 << 22;
+
+/// Every thread counting with a table needs its own buffer as big as the table, so big tables
+/// are shared by fewer threads to keep these buffers to this many entries in total.
+const MAX_BUFFERS: usize = 1 << 24;
+
+fn gcd(a: i32, b: i32) -> i32 {
+    if b == 0 { a.abs() } else { gcd(b, a % b) }
+}
+
+/// A direction on the grid, stored as the smallest whole-number step that goes that way.
+///
+/// Directions are ordered clockwise starting from straight up, which is towards decreasing y.
+#[derive(Copy, Clone, Hash, Eq, PartialEq, Debug)]
+pub struct Direction {
+    x: i32,
+    y: i32,
+}
+
+impl Direction {
+    pub fn new(x: i32, y: i32) -> Direction {
+        let d = gcd(x, y).max(1);
+        Direction { x: x / d, y: y / d }
+    }
+
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+
+    /// Which quarter turn clockwise from up this direction falls in, counting the direction at
+    /// the start of each quarter as part of it.
+    fn quadrant(&self) -> u8 {
+        match (self.x, self.y) {
+            (x, y) if x >= 0 && y < 0 => 0,
+            (x, y) if x > 0 && y >= 0 => 1,
+            (x, y) if x <= 0 && y > 0 => 2,
+            _ => 3,
+        }
+    }
+}
+
+impl PartialOrd for Direction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Direction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Within a quadrant, `other` is further clockwise exactly when the cross product is
+        // positive, since y points down.
+        let cross = i64::from(self.x) * i64::from(other.y) - i64::from(self.y) * i64::from(other.x);
+        self.quadrant().cmp(&other.quadrant()).then(0.cmp(&cross))
+    }
+}
+
+/// Maps every offset between two asteroids of a field to the index of its direction, so that
+/// directions can be told apart without any arithmetic or hashing per pair.
+struct DirectionTable {
+    width: i32,
+    height: i32,
+    directions: Vec<u32>,
+}
+
+impl DirectionTable {
+    /// A table for offsets of up to `width` across and `height` down, or `None` if it would
+    /// be too big.
+    fn new(width: i32, height: i32) -> Option<DirectionTable> {
+        let len = (2 * width as usize + 1).checked_mul(2 * height as usize + 1)?;
+        if len > MAX_TABLE {
+            return None;
+        }
+        let mut table = DirectionTable { width, height, directions: Vec::with_capacity(len) };
+        for dy in -height..=height {
+            for dx in -width..=width {
+                let d = Direction::new(dx, dy);
+                let index = table.index(d.x, d.y) as u32;
+                table.directions.push(index);
+            }
+        }
+        Some(table)
+    }
+
+    fn index(&self, dx: i32, dy: i32) -> usize {
+        ((dy + self.height) * (2 * self.width + 1) + dx + self.width) as usize
+    }
+
+    fn len(&self) -> usize {
+        self.directions.len()
+    }
+}
+
+fn count_with_table(asteroids: &[(i32, i32)], station: (i32, i32), table: &DirectionTable, seen: &mut [u32], stamp: u32) -> usize {
+    let mut count = 0;
+    for &(x, y) in asteroids {
+        if (x, y) != station {
+            let direction = table.directions[table.index(x - station.0, y - station.1)] as usize;
+            if seen[direction] != stamp {
+                seen[direction] = stamp;
+                count += 1;
+            }
+        }
+    }
+    count
+}
+
+fn count_with_hashing(asteroids: &[(i32, i32)], station: (i32, i32)) -> usize {
+    asteroids.iter()
+        .filter(|a| **a != station)
+        .map(|(x, y)| Direction::new(x - station.0, y - station.1))
+        .collect::<HashSet<_>>()
+        .len()
+}
+
+/// How many threads to use when each one needs a buffer of `table_len` entries.
+fn workers(threads: usize, table_len: Option<usize>) -> usize {
+    match table_len {
+        Some(len) => threads.min(MAX_BUFFERS / len.max(1)),
+        None => threads,
+    }.max(1)
+}
+
+/// Counts how many other asteroids each asteroid can see, spreading the work over up to
+/// `threads` threads.
+///
+/// An asteroid sees exactly one other asteroid in every direction that has any, so this is the
+/// number of distinct directions to the others. Every asteroid still looks at every other one,
+/// so this takes O(n²) time: looking up a direction in the table is much cheaper than sorting
+/// or hashing, but ten times the asteroids still takes a hundred times as long. Whether the
+/// first asteroid in some direction hides the rest depends on every pair along that line, and
+/// nothing here counts those without visiting the pairs, so this is not sub-quadratic.
+pub fn visible_counts(asteroids: &[(i32, i32)], threads: usize) -> Vec<usize> {
+    let mut counts = vec![0; asteroids.len()];
+    if asteroids.is_empty() {
+        return counts;
+    }
+    let width = asteroids.iter().map(|(x, _)| *x).max().unwrap() - asteroids.iter().map(|(x, _)| *x).min().unwrap();
+    let height = asteroids.iter().map(|(_, y)| *y).max().unwrap() - asteroids.iter().map(|(_, y)| *y).min().unwrap();
+    let table = DirectionTable::new(width, height);
+    let table = table.as_ref();
+
+    let chunk = asteroids.len().div_ceil(workers(threads, table.map(|table| table.len())));
+    thread::scope(|scope| {
+        for (stations, counts) in asteroids.chunks(chunk).zip(counts.chunks_mut(chunk)) {
+            scope.spawn(move || {
+                let mut seen = vec![0; table.map_or(0, |table| table.len())];
+                for (stamp, (station, count)) in stations.iter().zip(counts.iter_mut()).enumerate() {
+                    *count = match table {
+                        Some(table) => count_with_table(asteroids, *station, table, &mut seen, stamp as u32 + 1),
+                        None => count_with_hashing(asteroids, *station),
+                    };
+                }
+            });
+        }
+    });
+    counts
+}
+
+/// The asteroid that can see the most others, and how many it sees. Of several equally good
+/// asteroids, the last one is chosen.
+pub fn best(asteroids: &[(i32, i32)]) -> Option<((i32, i32), usize)> {
+    let threads = thread::available_parallelism().map_or(1, |n| n.get());
+    asteroids.iter()
+        .copied()
+        .zip(visible_counts(asteroids, threads))
+        .max_by_key(|(_, count)| *count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // .#..#
+    // .....
+    // #####
+    // ....#
+    // ...##
+    const FIELD: [(i32, i32); 10] = [(1, 0), (4, 0), (0, 2), (1, 2), (2, 2), (3, 2), (4, 2), (4, 3), (3, 4), (4, 4)];
+
+    #[test]
+    fn direction_exact() {
+        assert_eq!(Direction::new(-6, 4), Direction::new(-3, 2));
+        assert_eq!((Direction::new(-6, 4).x(), Direction::new(-6, 4).y()), (-3, 2));
+        assert_eq!(Direction::new(0, -7), Direction::new(0, -1));
+        // Far too close together for floating point angles to tell apart reliably.
+        let d1 = Direction::new(100_000, 99_999);
+        let d2 = Direction::new(99_999, 99_998);
+        assert_ne!(d1, d2);
+        assert_eq!(d1.cmp(&d2), Ordering::Greater);
+    }
+
+    #[test]
+    fn counts() {
+        let expected = vec![7, 7, 6, 7, 7, 7, 5, 7, 8, 7];
+        assert_eq!(visible_counts(&FIELD, 1), expected);
+        assert_eq!(visible_counts(&FIELD, 3), expected);
+        assert_eq!(visible_counts(&FIELD, 20), expected);
+        assert_eq!(best(&FIELD), Some(((3, 4), 8)));
+        assert_eq!(best(&[]), None);
+    }
+
+    #[test]
+    fn buffer_budget() {
+        assert_eq!(workers(8, None), 8);
+        assert_eq!(workers(8, Some(1000)), 8);
+        assert_eq!(workers(64, Some(MAX_TABLE)), MAX_BUFFERS / MAX_TABLE);
+        assert_eq!(workers(0, Some(MAX_TABLE)), 1);
+    }
+
+    #[test]
+    fn sparse_field() {
+        // Too spread out for a table, so directions are hashed instead.
+        let field = [(0, 0), (100_000, 100_000), (200_000, 200_000), (0, 5_000)];
+        assert_eq!(visible_counts(&field, 2), vec![2, 3, 2, 3]);
+        for (station, count) in field.iter().zip(visible_counts(&field, 2)) {
+            assert_eq!(count_with_hashing(&field, *station), count);
+        }
+    }
+}
@@ -1,8 +1,12 @@
 use std::result::Result;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::collections::{HashSet, VecDeque};
 use simple_error::SimpleError;
 use crossbeam_channel::{Sender, Receiver};
+use crate::word::Word;
+use crate::memory::{MemoryPolicy, MemoryError, PagedMemory};
+use crate::session::{RecordedInput, Session};
 
 #[derive(Copy, Clone, Debug)]
 struct ComputeError {}
@@ -15,89 +19,245 @@ impl Display for ComputeError {
 
 impl std::error::Error for ComputeError {}
 
+/// An addition or multiplication whose result does not fit in a word.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OverflowError {
+    pub ip: usize,
+}
+
+impl Display for OverflowError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "arithmetic overflow at ip={}", self.ip)
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
 #[derive(Debug)]
-enum Instruction {
-    Add(Parameter, Parameter, Parameter),
-    Mult(Parameter, Parameter, Parameter),
-    Input(Parameter),
-    Output(Parameter),
-    JumpIfTrue(Parameter, Parameter),
-    JumpIfFalse(Parameter, Parameter),
-    LessThan(Parameter, Parameter, Parameter),
-    Equals(Parameter, Parameter, Parameter),
-    ModifyRelativeBase(Parameter),
+enum Instruction<W> {
+    Add(Parameter<W>, Parameter<W>, Parameter<W>),
+    Mult(Parameter<W>, Parameter<W>, Parameter<W>),
+    Input(Parameter<W>),
+    Output(Parameter<W>),
+    JumpIfTrue(Parameter<W>, Parameter<W>),
+    JumpIfFalse(Parameter<W>, Parameter<W>),
+    LessThan(Parameter<W>, Parameter<W>, Parameter<W>),
+    Equals(Parameter<W>, Parameter<W>, Parameter<W>),
+    ModifyRelativeBase(Parameter<W>),
     Exit,
 }
 
 #[derive(Debug)]
-enum Parameter {
-    Immediate(i64),
+enum Parameter<W> {
+    Immediate(W),
     Position(usize),
     Relative(isize),
 }
 
-impl Parameter {
-    fn new(value: i64, mode_bit: i32) -> Result<Parameter, SimpleError> {
+impl<W: Word> Parameter<W> {
+    fn new(value: W, mode_bit: i32) -> Result<Parameter<W>, SimpleError> {
         match mode_bit {
-            0 => {
-                if value < 0 {
-                    Err(SimpleError::new(format!("Negative index: {}", value)))
-                } else {
-                    Ok(Parameter::Position(value as usize))
-                }
-            }
+            0 => Ok(Parameter::Position(to_address(&value)?)),
             1 => Ok(Parameter::Immediate(value)),
-            2 => Ok(Parameter::Relative(value as isize)),
+            2 => {
+                let offset = value.to_i64()
+                    .ok_or_else(|| SimpleError::new(format!("Offset out of range: {}", value)))?;
+                Ok(Parameter::Relative(offset as isize))
+            }
             _ => Err(SimpleError::new("Invalid mode bit")),
         }
     }
 }
 
-pub struct Computer {
+fn to_address<W: Word>(value: &W) -> Result<usize, SimpleError> {
+    match value.to_i64() {
+        Some(x) if x >= 0 => Ok(x as usize),
+        _ => Err(SimpleError::new(format!("Invalid address: {}", value))),
+    }
+}
+
+/// An Intcode virtual machine operating on words of type `W`.
+///
+/// Memory starts out as the loaded program and grows on demand; cells beyond the end of the
+/// program read as zero unless the `MemoryPolicy` says otherwise. Memory is copy-on-write, so
+/// `fork` is cheap even for programs that use a lot of it.
+pub struct Computer<W: Word = i64> {
     ip: usize,
     rb: isize,
-    memory: Vec<i64>,
-    input: Receiver<i64>,
+    memory: PagedMemory<W>,
+    program_len: usize,
+    policy: MemoryPolicy,
+    written: HashSet<usize>,
+    instructions: u64,
+    recording: Option<Session<W>>,
+    replaying: Option<VecDeque<RecordedInput<W>>>,
+    input: Receiver<W>,
     input_request: Sender<()>,
-    output: Sender<i64>,
+    output: Sender<W>,
 }
 
-impl std::fmt::Debug for Computer {
+impl<W: Word> std::fmt::Debug for Computer<W> {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
-        write!(f, "Computer{{ip={:?}, memory_at_ip={:?}}}", self.ip, self.memory[self.ip])
+        write!(f, "Computer{{ip={:?}, memory_at_ip={:?}}}", self.ip, self.memory.get(self.ip))
     }
 }
 
-impl Computer {
+impl<W: Word> Computer<W> {
     pub fn new(
-        memory: Vec<i64>,
-        input: Receiver<i64>,
+        memory: Vec<W>,
+        input: Receiver<W>,
         input_request: Sender<()>,
-        output: Sender<i64>) -> Computer {
-        let mut large_memory = vec![0; 1_000_000_000];
-        large_memory[..memory.len()].copy_from_slice(&memory);
+        output: Sender<W>) -> Computer<W> {
         Computer {
             ip: 0,
             rb: 0,
-            memory: large_memory,
+            program_len: memory.len(),
+            memory: PagedMemory::new(memory),
+            policy: MemoryPolicy::default(),
+            written: HashSet::new(),
+            instructions: 0,
+            recording: None,
+            replaying: None,
             input,
             input_request,
             output,
         }
     }
 
-    pub fn run_to_completion(&mut self) -> Result<i64, Box<dyn Error>> {
+    /// Creates a computer with no I/O attached. Any input or output instruction fails unless the
+    /// computer is run through `outputs`.
+    pub fn load(memory: Vec<W>) -> Computer<W> {
+        let (_, input) = crossbeam_channel::unbounded();
+        let (input_request, _) = crossbeam_channel::unbounded();
+        let (output, _) = crossbeam_channel::unbounded();
+        Computer::new(memory, input, input_request, output)
+    }
+
+    /// Copies the current state of this computer, with no I/O attached. Memory pages are shared
+    /// until one of the two computers writes to them.
+    pub fn fork(&self) -> Computer<W> {
+        let (_, input) = crossbeam_channel::unbounded();
+        let (input_request, _) = crossbeam_channel::unbounded();
+        let (output, _) = crossbeam_channel::unbounded();
+        Computer {
+            ip: self.ip,
+            rb: self.rb,
+            memory: self.memory.clone(),
+            program_len: self.program_len,
+            policy: self.policy,
+            written: self.written.clone(),
+            instructions: self.instructions,
+            recording: self.recording.clone(),
+            replaying: self.replaying.clone(),
+            input,
+            input_request,
+            output,
+        }
+    }
+
+    /// Restricts memory accesses made by this computer. Violations stop the program with a
+    /// `MemoryError`.
+    pub fn with_memory_policy(mut self, policy: MemoryPolicy) -> Computer<W> {
+        self.policy = policy;
+        self
+    }
+
+    /// Overwrites one word of the program before it runs, regardless of the memory policy.
+    pub fn with_patch(mut self, address: usize, value: W) -> Computer<W> {
+        self.memory.set(address, value);
+        self.written.insert(address);
+        self
+    }
+
+    /// Replaces this computer's I/O channels, for example to resume a fork.
+    pub fn with_io(mut self, input: Receiver<W>, input_request: Sender<()>, output: Sender<W>) -> Computer<W> {
+        self.input = input;
+        self.input_request = input_request;
+        self.output = output;
+        self
+    }
+
+    /// Records every input this computer consumes. The recording can be retrieved with
+    /// `take_session`.
+    pub fn with_recording(mut self) -> Computer<W> {
+        self.recording = Some(Session::new());
+        self
+    }
+
+    /// Feeds this computer's inputs from a recorded session instead of its input channel.
+    ///
+    /// Each input must be requested after exactly the same number of instructions as when it was
+    /// recorded, otherwise the program has diverged and stops with an error.
+    pub fn replaying(mut self, session: Session<W>) -> Computer<W> {
+        self.replaying = Some(session.inputs.into_iter().collect());
+        self
+    }
+
+    /// Returns the inputs recorded so far, leaving an empty recording in its place.
+    pub fn take_session(&mut self) -> Option<Session<W>> {
+        self.recording.as_mut().map(std::mem::take)
+    }
+
+    pub fn memory(&self) -> &PagedMemory<W> {
+        &self.memory
+    }
+
+    /// The number of instructions executed so far.
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    /// Runs the program with a fixed sequence of inputs, yielding each output as it is produced.
+    ///
+    /// Requesting more input than was given stops the iterator with an error.
+    pub fn outputs<I: IntoIterator<Item=W>>(mut self, inputs: I) -> Outputs<W> {
+        let (input_sender, input) = crossbeam_channel::unbounded();
+        for val in inputs {
+            input_sender.send(val).unwrap();
+        }
+        let (input_request, input_requests) = crossbeam_channel::unbounded();
+        let (output_sender, output) = crossbeam_channel::unbounded();
+        self.input = input;
+        self.input_request = input_request;
+        self.output = output_sender;
+        Outputs {
+            computer: self,
+            output,
+            _input_requests: input_requests,
+            done: false,
+        }
+    }
+
+    pub fn run_to_completion(&mut self) -> Result<W, Box<dyn Error>> {
         loop {
             if self.step()? {
                 break;
             }
         }
-        Ok(self.memory[0])
+        Ok(self.read(0)?)
+    }
+
+    /// Runs until the program halts, returning `true`, or until it is about to read an input
+    /// that has not been sent yet, returning `false`. Calling it again after sending the input
+    /// continues from there.
+    pub fn run_until_input(&mut self) -> Result<bool, Box<dyn Error>> {
+        loop {
+            let waiting = self.replaying.is_none()
+                && self.input.is_empty()
+                && self.read(self.ip)?.to_i64().map(|code| code % 100) == Some(3);
+            if waiting {
+                return Ok(false);
+            }
+            if self.step()? {
+                return Ok(true);
+            }
+        }
     }
 
     fn step(&mut self) -> Result<bool, Box<dyn Error>> {
-        let opcode = self.memory[self.ip as usize] % 100;
-        let mut m = self.memory[self.ip as usize] / 100;
+        let code = self.read(self.ip)?.to_i64().ok_or(ComputeError {})?;
+        let opcode = code % 100;
+        let mut m = code / 100;
         let mut modes = Vec::new();
         for _ in 0..3 {
             modes.push((m % 10) as i32);
@@ -107,286 +267,419 @@ impl Computer {
         let instruction =
             match opcode {
                 1 => Instruction::Add(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?,
-                    Parameter::new((self.memory[self.ip + 2]) as i64, modes[1])?,
-                    Parameter::new((self.memory[self.ip + 3]) as i64, modes[2])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?,
+                    Parameter::new(self.read(self.ip + 2)?, modes[1])?,
+                    Parameter::new(self.read(self.ip + 3)?, modes[2])?),
                 2 => Instruction::Mult(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?,
-                    Parameter::new((self.memory[self.ip + 2]) as i64, modes[1])?,
-                    Parameter::new((self.memory[self.ip + 3]) as i64, modes[2])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?,
+                    Parameter::new(self.read(self.ip + 2)?, modes[1])?,
+                    Parameter::new(self.read(self.ip + 3)?, modes[2])?),
                 3 => Instruction::Input(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?),
                 4 => Instruction::Output(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?),
                 5 => Instruction::JumpIfTrue(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?,
-                    Parameter::new((self.memory[self.ip + 2]) as i64, modes[1])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?,
+                    Parameter::new(self.read(self.ip + 2)?, modes[1])?),
                 6 => Instruction::JumpIfFalse(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?,
-                    Parameter::new((self.memory[self.ip + 2]) as i64, modes[1])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?,
+                    Parameter::new(self.read(self.ip + 2)?, modes[1])?),
                 7 => Instruction::LessThan(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?,
-                    Parameter::new((self.memory[self.ip + 2]) as i64, modes[1])?,
-                    Parameter::new((self.memory[self.ip + 3]) as i64, modes[2])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?,
+                    Parameter::new(self.read(self.ip + 2)?, modes[1])?,
+                    Parameter::new(self.read(self.ip + 3)?, modes[2])?),
                 8 => Instruction::Equals(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?,
-                    Parameter::new((self.memory[self.ip + 2]) as i64, modes[1])?,
-                    Parameter::new((self.memory[self.ip + 3]) as i64, modes[2])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?,
+                    Parameter::new(self.read(self.ip + 2)?, modes[1])?,
+                    Parameter::new(self.read(self.ip + 3)?, modes[2])?),
                 9 => Instruction::ModifyRelativeBase(
-                    Parameter::new((self.memory[self.ip + 1]) as i64, modes[0])?),
+                    Parameter::new(self.read(self.ip + 1)?, modes[0])?),
                 99 => Instruction::Exit,
                 _ => return Err(Box::new(ComputeError {}))
             };
         match instruction {
             Instruction::Add(x, y, dest) => {
-                let x = self.resolve(x);
-                let y = self.resolve(y);
-                let dest = self.resolve_save(dest) as usize;
-                self.memory[dest] = x + y;
+                let x = self.resolve(x)?;
+                let y = self.resolve(y)?;
+                let dest = self.resolve_save(dest)?;
+                let sum = x.checked_add(&y).ok_or(OverflowError { ip: self.ip })?;
+                self.write(dest, sum)?;
                 self.ip += 4;
             }
             Instruction::Mult(x, y, dest) => {
-                let x = self.resolve(x);
-                let y = self.resolve(y);
-                let dest = self.resolve_save(dest) as usize;
-                self.memory[dest] = x * y;
+                let x = self.resolve(x)?;
+                let y = self.resolve(y)?;
+                let dest = self.resolve_save(dest)?;
+                let product = x.checked_mul(&y).ok_or(OverflowError { ip: self.ip })?;
+                self.write(dest, product)?;
                 self.ip += 4;
             }
             Instruction::Input(dest) => {
-                let dest = self.resolve_save(dest) as usize;
-                println!("sending input request");
-                self.input_request.send(())?;
-                let val = self.input.recv()?;
-                println!("received {}", val);
-                self.memory[dest] = val;
+                let dest = self.resolve_save(dest)?;
+                let val = match self.replaying.as_mut() {
+                    Some(replay) => {
+                        let recorded = replay.pop_front()
+                            .ok_or_else(|| SimpleError::new("Replay ran out of inputs"))?;
+                        if recorded.instruction != self.instructions {
+                            return Err(Box::new(SimpleError::new(format!(
+                                "Replay diverged: input recorded at instruction {} requested at {}",
+                                recorded.instruction, self.instructions))));
+                        }
+                        recorded.value
+                    }
+                    None => {
+                        self.input_request.send(())?;
+                        self.input.recv()?
+                    }
+                };
+                if let Some(session) = self.recording.as_mut() {
+                    session.inputs.push(RecordedInput { instruction: self.instructions, value: val.clone() });
+                }
+                self.write(dest, val)?;
                 self.ip += 2;
             }
             Instruction::Output(x) => {
-                let val = self.resolve(x);
+                let val = self.resolve(x)?;
                 self.output.send(val)?;
                 self.ip += 2;
             }
             Instruction::JumpIfTrue(test, loc) => {
-                if self.resolve(test) != 0 {
-                    self.ip = self.resolve(loc) as usize;
+                if self.resolve(test)? != W::zero() {
+                    self.ip = to_address(&self.resolve(loc)?)?;
                 } else {
                     self.ip += 3;
                 }
             }
             Instruction::JumpIfFalse(test, loc) => {
-                if self.resolve(test) == 0 {
-                    self.ip = self.resolve(loc) as usize;
+                if self.resolve(test)? == W::zero() {
+                    self.ip = to_address(&self.resolve(loc)?)?;
                 } else {
                     self.ip += 3;
                 }
             }
             Instruction::LessThan(x, y, dest) => {
-                let dest = self.resolve_save(dest) as usize;
-                if self.resolve(x) < self.resolve(y) {
-                    self.memory[dest] = 1;
+                let dest = self.resolve_save(dest)?;
+                if self.resolve(x)? < self.resolve(y)? {
+                    self.write(dest, W::one())?;
                 } else {
-                    self.memory[dest] = 0;
+                    self.write(dest, W::zero())?;
                 }
                 self.ip += 4
             }
             Instruction::Equals(x, y, dest) => {
-                let dest = self.resolve_save(dest) as usize;
-                if self.resolve(x) == self.resolve(y) {
-                    self.memory[dest] = 1;
+                let dest = self.resolve_save(dest)?;
+                if self.resolve(x)? == self.resolve(y)? {
+                    self.write(dest, W::one())?;
                 } else {
-                    self.memory[dest] = 0;
+                    self.write(dest, W::zero())?;
                 }
                 self.ip += 4
             }
             Instruction::ModifyRelativeBase(x) => {
-                self.rb += self.resolve(x) as isize;
+                let offset = self.resolve(x)?.to_i64().ok_or(ComputeError {})?;
+                self.rb = self.rb.checked_add(offset as isize).ok_or(OverflowError { ip: self.ip })?;
                 self.ip += 2;
             }
             Instruction::Exit => return Ok(true),
-            _ => {
-                return Err(Box::new(ComputeError {}));
-            }
         }
 
+        self.instructions += 1;
         Ok(false)
     }
 
-    fn resolve(&self, x: Parameter) -> i64 {
+    fn read(&self, address: usize) -> Result<W, MemoryError> {
+        self.check_address(address)?;
+        if self.policy.trap_uninitialized_reads
+            && address >= self.program_len
+            && !self.written.contains(&address) {
+            return Err(MemoryError::UninitializedRead { ip: self.ip, address });
+        }
+        Ok(self.memory.get(address))
+    }
+
+    fn write(&mut self, address: usize, value: W) -> Result<(), MemoryError> {
+        self.check_address(address)?;
+        if address < self.program_len {
+            if self.policy.read_only_code {
+                return Err(MemoryError::WriteToCode { ip: self.ip, address });
+            }
+        } else if self.policy.trap_uninitialized_reads {
+            self.written.insert(address);
+        }
+        self.memory.set(address, value);
+        Ok(())
+    }
+
+    fn check_address(&self, address: usize) -> Result<(), MemoryError> {
+        match self.policy.max_address {
+            Some(max) if address > max => Err(MemoryError::AddressOutOfRange { ip: self.ip, address }),
+            _ => Ok(()),
+        }
+    }
+
+    fn resolve(&self, x: Parameter<W>) -> Result<W, Box<dyn Error>> {
         match x {
-            Parameter::Immediate(x) => x,
-            Parameter::Position(x) => self.memory[x],
-            Parameter::Relative(x) => self.memory[(self.rb + x) as usize]
+            Parameter::Immediate(x) => Ok(x),
+            Parameter::Position(x) => Ok(self.read(x)?),
+            Parameter::Relative(x) => Ok(self.read(self.relative_address(x)?)?),
         }
     }
 
-    fn resolve_save(&self, x: Parameter) -> i64 {
+    fn resolve_save(&self, x: Parameter<W>) -> Result<usize, Box<dyn Error>> {
         match x {
-            Parameter::Immediate(x) =>  panic!("wat"),
-            Parameter::Position(x) => x as i64,
-            Parameter::Relative(x) => {
-                (self.rb + x) as i64
+            Parameter::Immediate(_) => Err(Box::new(SimpleError::new("Cannot write to an immediate parameter"))),
+            Parameter::Position(x) => Ok(x),
+            Parameter::Relative(x) => self.relative_address(x),
+        }
+    }
+
+    fn relative_address(&self, offset: isize) -> Result<usize, Box<dyn Error>> {
+        let address = self.rb.checked_add(offset).ok_or(OverflowError { ip: self.ip })?;
+        if address < 0 {
+            Err(Box::new(SimpleError::new(format!("Negative relative address: {}", address))))
+        } else {
+            Ok(address as usize)
+        }
+    }
+}
+
+/// Iterator over the outputs of a `Computer`, created by `Computer::outputs`.
+pub struct Outputs<W: Word> {
+    computer: Computer<W>,
+    output: Receiver<W>,
+    _input_requests: Receiver<()>,
+    done: bool,
+}
+
+impl<W: Word> Iterator for Outputs<W> {
+    type Item = Result<W, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Ok(val) = self.output.try_recv() {
+                return Some(Ok(val));
+            }
+            match self.computer.step() {
+                Ok(halted) => self.done = halted,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
             }
         }
+        self.output.try_recv().ok().map(Ok)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use num_bigint::BigInt;
 
     #[test]
-    fn first_example() {
+    fn narrow_words() {
         let (tx1, rx1) = crossbeam_channel::unbounded();
         let (tx2, rx2) = crossbeam_channel::unbounded();
-        let mut c =
-            Computer::new(
-                vec![1, 9, 10, 3,
-                     2, 3, 11, 0,
-                     99,
-                     30, 40, 50],
-                rx1,
-                tx2);
+        let (req_tx, _req_rx) = crossbeam_channel::unbounded();
+        tx1.send(7i32).unwrap();
+        let mut c = Computer::new(vec![3, 9, 1002, 9, 6, 9, 4, 9, 99, 0], rx1, req_tx, tx2);
         c.run_to_completion().unwrap();
-        assert_eq!(c.memory[..12], vec![3500, 9, 10, 70,
-                                        2, 3, 11, 0,
-                                        99,
-                                        30, 40, 50][..]);
+        assert_eq!(rx2.recv().unwrap(), 42i32);
     }
 
     #[test]
-    fn second_example() {
-        let (tx, rx) = crossbeam_channel::unbounded();
-        let mut c = Computer::new(vec![1, 0, 0, 0, 99], rx, tx);
+    fn large_calc_i128() {
+        let (_tx1, rx1) = crossbeam_channel::unbounded();
+        let (tx2, rx2) = crossbeam_channel::unbounded();
+        let (req_tx, _req_rx) = crossbeam_channel::unbounded();
+        let mut c: Computer<i128> =
+            Computer::new(vec![1102, 1219070632396864, 1219070632396864, 7, 4, 7, 99, 0], rx1, req_tx, tx2);
         c.run_to_completion().unwrap();
-        assert_eq!(c.memory[..5], vec![2, 0, 0, 0, 99][..]);
+        assert_eq!(rx2.recv().unwrap(), 1486133206772489918753597034496i128);
     }
 
     #[test]
-    fn third_example() {
-        let (tx, rx) = crossbeam_channel::unbounded();
-        let mut c = Computer::new(vec![2, 3, 0, 3, 99], rx, tx);
+    fn large_calc_bigint() {
+        let big: BigInt = "1486133206772489918753597034496".parse().unwrap();
+        let (_tx1, rx1) = crossbeam_channel::unbounded();
+        let (tx2, rx2) = crossbeam_channel::unbounded();
+        let (req_tx, _req_rx) = crossbeam_channel::unbounded();
+        let program = vec![
+            BigInt::from(1102), big.clone(), big.clone(), BigInt::from(7),
+            BigInt::from(4), BigInt::from(7),
+            BigInt::from(99),
+            BigInt::from(0)];
+        let mut c = Computer::new(program, rx1, req_tx, tx2);
         c.run_to_completion().unwrap();
-        assert_eq!(c.memory[..5], vec![2, 3, 0, 6, 99][..]);
+        assert_eq!(rx2.recv().unwrap(), &big * &big);
     }
 
     #[test]
-    fn fourth_example() {
-        let (tx, rx) = crossbeam_channel::unbounded();
-        let mut c = Computer::new(vec![2, 4, 4, 5, 99, 0], rx, tx);
-        c.run_to_completion().unwrap();
-        assert_eq!(c.memory[..6], vec![2, 4, 4, 5, 99, 9801][..]);
+    fn overflow() {
+        let program: Vec<i32> = vec![1101, 1, 1, 0, 1102, i32::MAX, 2, 0, 99];
+        let err = Computer::load(program).run_to_completion().unwrap_err();
+        assert_eq!(*err.downcast_ref::<OverflowError>().unwrap(), OverflowError { ip: 4 });
+
+        let program: Vec<i64> = vec![1101, i64::MAX, 1, 0, 99];
+        let err = Computer::load(program).run_to_completion().unwrap_err();
+        assert_eq!(*err.downcast_ref::<OverflowError>().unwrap(), OverflowError { ip: 0 });
+
+        // Moving the relative base, and addressing relative to it.
+        for program in [vec![109, i64::MAX, 109, 1, 99], vec![109, i64::MAX, 204, 1, 99], vec![109, i64::MAX, 21101, 0, 0, 1, 99]] {
+            let err = Computer::load(program).run_to_completion().unwrap_err();
+            assert_eq!(*err.downcast_ref::<OverflowError>().unwrap(), OverflowError { ip: 2 });
+        }
     }
 
-    #[test]
-    fn fifth_example() {
-        let (tx, rx) = crossbeam_channel::unbounded();
-        let mut c = Computer::new(vec![1, 1, 1, 4, 99, 5, 6, 0, 99], rx, tx);
-        c.run_to_completion().unwrap();
-        assert_eq!(c.memory[..9], vec![30, 1, 1, 4, 2, 5, 6, 0, 99][..]);
+    fn policy_error(program: Vec<i64>, policy: MemoryPolicy) -> MemoryError {
+        let (_tx1, rx1) = crossbeam_channel::unbounded();
+        let (tx2, _rx2) = crossbeam_channel::unbounded();
+        let (req_tx, _req_rx) = crossbeam_channel::unbounded();
+        let mut c = Computer::new(program, rx1, req_tx, tx2).with_memory_policy(policy);
+        let err = c.run_to_completion().unwrap_err();
+        *err.downcast_ref::<MemoryError>().unwrap()
     }
 
     #[test]
-    fn input() {
-        let (tx, rx) = crossbeam_channel::unbounded();
-        tx.send(10).unwrap();
-        let mut c = Computer::new(vec![3, 0, 99], rx, tx);
-        c.run_to_completion().unwrap();
-        assert_eq!(c.memory[..3], vec![10, 0, 99][..]);
+    fn max_address() {
+        let policy = MemoryPolicy { max_address: Some(100), ..MemoryPolicy::default() };
+        assert_eq!(
+            policy_error(vec![1101, 1, 1, 50, 109, 200, 21101, 1, 1, 0, 99], policy),
+            MemoryError::AddressOutOfRange { ip: 6, address: 200 });
     }
 
     #[test]
-    fn output() {
-        let (tx1, rx1) = crossbeam_channel::unbounded();
-        let (tx2, rx2) = crossbeam_channel::unbounded();
-        let mut c = Computer::new(vec![4, 0, 99], rx1, tx2);
-        c.run_to_completion().unwrap();
-        assert_eq!(c.memory[..3], vec![4, 0, 99][..]);
-        assert_eq!(rx2.recv().unwrap(), 4)
+    fn read_only_code() {
+        let policy = MemoryPolicy { read_only_code: true, ..MemoryPolicy::default() };
+        assert_eq!(
+            policy_error(vec![1101, 1, 1, 9, 1101, 2, 2, 1, 99], policy),
+            MemoryError::WriteToCode { ip: 4, address: 1 });
     }
 
-//    #[test]
-//    fn immediate_mode() {
-//        let (tx1, rx1) = crossbeam_channel::unbounded();
-//        let (tx2, rx2) = crossbeam_channel::unbounded();
-//        let mut c = Computer::new(vec![101, 2, 1, 0, 99], rx1, tx2);
-//        c.run_to_completion().unwrap();
-//        assert_eq!(c.memory, vec![3, 2, 1, 0, 99]);
-//    }
+    #[test]
+    fn uninitialized_read() {
+        let policy = MemoryPolicy { trap_uninitialized_reads: true, ..MemoryPolicy::default() };
+        assert_eq!(
+            policy_error(vec![1101, 1, 1, 20, 1, 20, 21, 22, 99], policy),
+            MemoryError::UninitializedRead { ip: 4, address: 21 });
+    }
 
     #[test]
-    fn equals() {
-        let (tx1, rx1) = crossbeam_channel::unbounded();
+    fn permissive_policy() {
+        let (_tx1, rx1) = crossbeam_channel::unbounded();
         let (tx2, rx2) = crossbeam_channel::unbounded();
-        tx1.send(8);
-        let mut c = Computer::new(vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], rx1, tx2);
+        let (req_tx, _req_rx) = crossbeam_channel::unbounded();
+        let policy = MemoryPolicy {
+            max_address: Some(20),
+            read_only_code: true,
+            trap_uninitialized_reads: true,
+        };
+        let mut c: Computer =
+            Computer::new(vec![1101, 1, 1, 20, 4, 20, 99], rx1, req_tx, tx2).with_memory_policy(policy);
         c.run_to_completion().unwrap();
-        assert_eq!(rx2.recv().unwrap(), 1);
+        assert_eq!(rx2.recv().unwrap(), 2);
     }
 
     #[test]
-    fn equals_immediate() {
-        let (tx1, rx1) = crossbeam_channel::unbounded();
-        let (tx2, rx2) = crossbeam_channel::unbounded();
-        tx1.send(8);
-        let mut c = Computer::new(vec![3, 3, 1108, -1, 8, 3, 4, 3, 99], rx1, tx2);
-        c.run_to_completion().unwrap();
-        assert_eq!(rx2.recv().unwrap(), 1);
+    fn outputs() {
+        let c: Computer = Computer::load(vec![3, 11, 4, 11, 1001, 11, 1, 11, 4, 11, 99, 0]);
+        let outputs: Vec<i64> = c.outputs(vec![41]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(outputs, vec![41, 42]);
     }
 
     #[test]
-    fn less_than() {
-        let (tx1, rx1) = crossbeam_channel::unbounded();
-        let (tx2, rx2) = crossbeam_channel::unbounded();
-        tx1.send(8);
-        let mut c = Computer::new(vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8], rx1, tx2);
-        c.run_to_completion().unwrap();
-        assert_eq!(rx2.recv().unwrap(), 0);
+    fn outputs_quine() {
+        let program = vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+        let c: Computer = Computer::load(program.clone());
+        let outputs: Vec<i64> = c.outputs(vec![]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(outputs, program);
     }
 
     #[test]
-    fn less_than_immediate() {
-        let (tx1, rx1) = crossbeam_channel::unbounded();
-        let (tx2, rx2) = crossbeam_channel::unbounded();
-        tx1.send(8);
-        let mut c = Computer::new(vec![3, 3, 1107, -1, 8, 3, 4, 3, 99], rx1, tx2);
-        c.run_to_completion().unwrap();
-        assert_eq!(rx2.recv().unwrap(), 0);
+    fn outputs_missing_input() {
+        let c: Computer = Computer::load(vec![4, 0, 3, 0, 99]);
+        let mut outputs = c.outputs(vec![]);
+        assert_eq!(outputs.next().unwrap().unwrap(), 4);
+        assert!(outputs.next().unwrap().is_err());
+        assert!(outputs.next().is_none());
     }
 
     #[test]
-    fn jump() {
+    fn record_and_replay() {
+        // Reads numbers and outputs their running total until it reads a zero.
+        let program = vec![3, 17, 1006, 17, 16, 1, 17, 18, 18, 4, 18, 1105, 1, 0, 0, 0, 99, 0, 0];
         let (tx1, rx1) = crossbeam_channel::unbounded();
         let (tx2, rx2) = crossbeam_channel::unbounded();
-        tx1.send(40);
-        let mut c = Computer::new(vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9], rx1, tx2);
+        let (req_tx, _req_rx) = crossbeam_channel::unbounded();
+        for val in &[3, 4, 5, 0] {
+            tx1.send(*val).unwrap();
+        }
+        let mut c = Computer::new(program.clone(), rx1, req_tx, tx2).with_recording();
         c.run_to_completion().unwrap();
-        assert_eq!(rx2.recv().unwrap(), 1);
+        let recorded_outputs: Vec<i64> = rx2.try_iter().collect();
+        assert_eq!(recorded_outputs, vec![3, 7, 12]);
+
+        let mut file = Vec::new();
+        c.take_session().unwrap().save(&mut file).unwrap();
+        let session = Session::load(&file[..]).unwrap();
+        assert_eq!(session.inputs[1], RecordedInput { instruction: 5, value: 4 });
+
+        let replayed_outputs: Vec<i64> =
+            Computer::load(program).replaying(session).outputs(vec![]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(replayed_outputs, recorded_outputs);
     }
 
     #[test]
-    fn relative_mode() {
-        let (tx1, rx1) = crossbeam_channel::unbounded();
-        let (tx2, rx2) = crossbeam_channel::unbounded();
-        let mut c = Computer::new(vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99], rx1, tx2);
-        c.run_to_completion().unwrap();
-        assert_eq!(rx2.recv().unwrap(), 109);
+    fn replay_divergence() {
+        let session = Session { inputs: vec![RecordedInput { instruction: 1, value: 5 }] };
+        let c: Computer = Computer::load(vec![3, 0, 99]).replaying(session);
+        let err = c.outputs(vec![]).next().unwrap().unwrap_err();
+        assert!(err.to_string().contains("diverged"));
     }
 
     #[test]
-    fn large_num() {
-        let (tx1, rx1) = crossbeam_channel::unbounded();
-        let (tx2, rx2) = crossbeam_channel::unbounded();
-        let mut c = Computer::new(vec![104, 1125899906842624, 99], rx1, tx2);
-        c.run_to_completion().unwrap();
-        assert_eq!(rx2.recv().unwrap(), 1125899906842624);
+    fn fork() {
+        // Stores 21, reads a number, then outputs the two added together.
+        let program = vec![1101, 20, 1, 13, 3, 14, 1, 13, 14, 14, 4, 14, 99, 0, 0];
+        let mut c: Computer = Computer::load(program);
+        c.step().unwrap();
+
+        let forked_outputs: Vec<i64> = c.fork().outputs(vec![5]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(forked_outputs, vec![26]);
+        assert_eq!(c.memory.to_vec()[13..], vec![21, 0][..]);
+
+        let outputs: Vec<i64> = c.outputs(vec![1]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(outputs, vec![22]);
     }
 
     #[test]
-    fn large_calc() {
-        let (tx1, rx1) = crossbeam_channel::unbounded();
-        let (tx2, rx2) = crossbeam_channel::unbounded();
-        let mut c = Computer::new(vec![1102, 34915192, 34915192, 7, 4, 7, 99, 0], rx1, tx2);
-        c.run_to_completion().unwrap();
-        assert_eq!(rx2.recv().unwrap(), 1219070632396864);
+    fn with_patch() {
+        // Outputs memory[5], which is read-only code under the policy.
+        let program = vec![4, 5, 99, 0, 0, 1];
+        let policy = MemoryPolicy { read_only_code: true, ..MemoryPolicy::default() };
+        let c: Computer = Computer::load(program).with_memory_policy(policy).with_patch(5, 2);
+        let outputs: Vec<i64> = c.outputs(vec![]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(outputs, vec![2]);
+
+        // Outputs memory[10], which is past the program and so only readable once patched.
+        let policy = MemoryPolicy { trap_uninitialized_reads: true, ..MemoryPolicy::default() };
+        let c: Computer = Computer::load(vec![4, 10, 99]).with_memory_policy(policy).with_patch(10, 7);
+        let outputs: Vec<i64> = c.outputs(vec![]).collect::<Result<_, _>>().unwrap();
+        assert_eq!(outputs, vec![7]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn run_until_input() {
+        let (input_sender, input) = crossbeam_channel::unbounded();
+        let (input_request, _input_requests) = crossbeam_channel::unbounded();
+        let (output, outputs) = crossbeam_channel::unbounded();
+        let mut c: Computer = Computer::load(vec![3, 0, 4, 0, 99]);
+        assert!(!c.run_until_input().unwrap());
+        assert!(!c.run_until_input().unwrap());
+
+        let mut resumed = c.fork().with_io(input, input_request, output);
+        input_sender.send(5).unwrap();
+        assert!(resumed.run_until_input().unwrap());
+        assert_eq!(outputs.try_recv().unwrap(), 5);
+    }
+}
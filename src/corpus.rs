@@ -0,0 +1,161 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use simple_error::SimpleError;
+use crate::computer::Computer;
+
+/// A conformance test case for `Computer`, read from a fixture file.
+///
+/// Fixtures are made of `key: value` lines, and lines starting with `#` are comments:
+///
+/// ```text
+/// # Day 5: outputs 1 if the input equals 8.
+/// program: 3,9,8,9,10,9,4,9,99,-1,8
+/// inputs: 8
+/// outputs: 1
+/// ```
+///
+/// `program` is required. `inputs` and `outputs` default to empty. `memory`, if present, is
+/// compared against the start of the final memory. `result` is either `halt` (the default) or
+/// `error`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Fixture {
+    pub name: String,
+    pub program: Vec<i64>,
+    pub inputs: Vec<i64>,
+    pub outputs: Vec<i64>,
+    pub memory: Option<Vec<i64>>,
+    pub halts: bool,
+}
+
+fn parse_words(s: &str) -> Result<Vec<i64>, Box<dyn Error>> {
+    if s.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(s.split(',').map(|i| i.trim().parse()).collect::<Result<_, _>>()?)
+}
+
+impl Fixture {
+    pub fn parse(name: &str, s: &str) -> Result<Fixture, Box<dyn Error>> {
+        let mut program = None;
+        let mut fixture = Fixture {
+            name: name.to_string(),
+            program: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            memory: None,
+            halts: true,
+        };
+        for line in s.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = match line.find(':') {
+                Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+                None => return Err(Box::new(SimpleError::new(format!("{}: expected `key: value`, got {}", name, line)))),
+            };
+            match key {
+                "program" => program = Some(parse_words(value)?),
+                "inputs" => fixture.inputs = parse_words(value)?,
+                "outputs" => fixture.outputs = parse_words(value)?,
+                "memory" => fixture.memory = Some(parse_words(value)?),
+                "result" => fixture.halts = match value {
+                    "halt" => true,
+                    "error" => false,
+                    _ => return Err(Box::new(SimpleError::new(format!("{}: unknown result {}", name, value)))),
+                },
+                _ => return Err(Box::new(SimpleError::new(format!("{}: unknown key {}", name, key)))),
+            }
+        }
+        fixture.program = program.ok_or_else(|| SimpleError::new(format!("{}: missing program", name)))?;
+        Ok(fixture)
+    }
+
+    pub fn load(path: &Path) -> Result<Fixture, Box<dyn Error>> {
+        let name = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        Fixture::parse(&name, &fs::read_to_string(path)?)
+    }
+
+    /// Runs the fixture's program and describes the first way in which it misbehaved.
+    pub fn check(&self) -> Result<(), String> {
+        let (input_sender, input) = crossbeam_channel::unbounded();
+        let (output, output_receiver) = crossbeam_channel::unbounded();
+        let (input_request, _input_requests) = crossbeam_channel::unbounded();
+        for val in &self.inputs {
+            input_sender.send(*val).unwrap();
+        }
+        drop(input_sender);
+
+        let mut computer = Computer::new(self.program.clone(), input, input_request, output);
+        let result = computer.run_to_completion();
+        let outputs: Vec<i64> = output_receiver.try_iter().collect();
+
+        match (&result, self.halts) {
+            (Ok(_), false) => return Err(format!("{}: expected an error, but the program halted", self.name)),
+            (Err(e), true) => return Err(format!("{}: expected a halt, got error: {}", self.name, e)),
+            _ => {}
+        }
+        if outputs != self.outputs {
+            return Err(format!("{}: expected outputs {:?}, got {:?}", self.name, self.outputs, outputs));
+        }
+        if let Some(expected) = &self.memory {
+            let memory: Vec<i64> = (0..expected.len()).map(|address| computer.memory().get(address)).collect();
+            if memory != *expected {
+                return Err(format!("{}: expected memory {:?}, got {:?}", self.name, expected, memory));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Loads every `.fixture` file in `dir`, sorted by name.
+pub fn load_dir(dir: &Path) -> Result<Vec<Fixture>, Box<dyn Error>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "fixture") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths.iter().map(|path| Fixture::load(path)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn corpus() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/corpus");
+        let fixtures = load_dir(&dir).unwrap();
+        assert!(!fixtures.is_empty());
+        let failures: Vec<String> = fixtures.iter().filter_map(|f| f.check().err()).collect();
+        assert!(failures.is_empty(), "{} of {} fixtures failed:\n{}", failures.len(), fixtures.len(), failures.join("\n"));
+    }
+
+    #[test]
+    fn parse() {
+        let fixture = Fixture::parse("example", "# comment\nprogram: 3,0,99\ninputs: 5\nmemory: 5\nresult: halt\n").unwrap();
+        assert_eq!(fixture.program, vec![3, 0, 99]);
+        assert_eq!(fixture.inputs, vec![5]);
+        assert!(fixture.outputs.is_empty());
+        assert_eq!(fixture.memory, Some(vec![5]));
+        assert!(fixture.halts);
+        assert!(fixture.check().is_ok());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(Fixture::parse("missing", "inputs: 1").is_err());
+        assert!(Fixture::parse("unknown", "program: 99\nexpected: 1").is_err());
+        assert!(Fixture::parse("result", "program: 99\nresult: maybe").is_err());
+    }
+
+    #[test]
+    fn check_reports_mismatch() {
+        let fixture = Fixture::parse("wrong", "program: 104,1,99\noutputs: 2").unwrap();
+        assert!(fixture.check().unwrap_err().contains("expected outputs [2], got [1]"));
+    }
+}
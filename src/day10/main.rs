@@ -1,8 +1,12 @@
 use std::error::Error;
-use std::collections::{HashSet, BTreeSet};
+use std::collections::{BTreeSet, VecDeque};
+use std::io::Read;
 use simple_error::SimpleError;
 use std::cmp::Ordering;
-use std::fmt::{Debug, Formatter};
+use aoc::asteroids::{self, Direction};
+use visibility::Heatmap;
+
+mod visibility;
 
 #[derive(Copy, Clone, Hash, Eq, PartialEq, Ord, PartialOrd, Debug)]
 struct Point {
@@ -17,14 +21,14 @@ struct Vector {
 }
 
 impl Vector {
-    fn angle(&self) -> f64 {
-        (self.y as f64).atan2((self.x as f64))
+    fn direction(&self) -> Direction {
+        Direction::new(self.x, self.y)
     }
 }
 
 impl PartialEq for Vector {
     fn eq(&self, other: &Self) -> bool {
-        (self.angle() - other.angle()).abs() < 1e-5
+        self.direction() == other.direction()
     }
 }
 
@@ -38,22 +42,14 @@ impl PartialOrd for Vector {
 
 impl Ord for Vector {
     fn cmp(&self, other: &Self) -> Ordering {
-        match self.angle().partial_cmp(&other.angle()) {
-            Some(x) => x,
-            None => Ordering::Equal,
-        }
+        self.direction().cmp(&other.direction())
     }
 }
 
 fn best_asteroid(input: &str) -> Result<(i32, i32), Box<dyn Error>> {
-    let field: BTreeSet<Point> = get_field(input);
-    let coords =
-        field.iter()
-            .max_by_key(|Point { x, y }| {
-                get_angles(&field, (*x, *y)).len()
-            });
-    match coords {
-        Some(coords) => Ok((coords.x, coords.y)),
+    let field: Vec<(i32, i32)> = get_field(input).iter().map(|p| (p.x, p.y)).collect();
+    match asteroids::best(&field) {
+        Some((coords, _)) => Ok(coords),
         None => Err(Box::new(SimpleError::new("No asteroids found"))),
     }
 }
@@ -69,58 +65,149 @@ fn get_field(input: &str) -> BTreeSet<Point>{
         .collect()
 }
 
-fn get_angles(field: &BTreeSet<Point>, (station_x, station_y): (i32, i32)) -> Vec<(Point, f64)> {
+/// The directions from the station in which there is at least one asteroid, in clockwise order,
+/// each with one of the asteroids in it.
+fn get_angles(field: &BTreeSet<Point>, (station_x, station_y): (i32, i32)) -> Vec<(Point, Direction)> {
     let mut angles: Vec<_> =
         field.iter()
             .filter(|Point { x: ox, y: oy }| *ox != station_x || *oy != station_y)
-            .map(|p| (p, Vector { x: p.x - station_x, y: p.y - station_y }))
-            .map(|(p, v)| (*p, v.angle()))
+            .map(|p| (*p, Vector { x: p.x - station_x, y: p.y - station_y }.direction()))
             .collect();
-    angles.sort_by(|(_, x), (_, y)| {
-        if x < y {
-            Ordering::Less
-        } else if x > y{
-            Ordering::Greater
-        } else {
-            Ordering::Equal
-        }
-    });
-    angles.dedup_by(|(_, x), (_, y)| (*x - *y).abs() < 1e-5);
+    angles.sort_by_key(|(_, direction)| *direction);
+    angles.dedup_by_key(|(_, direction)| *direction);
     angles
 }
 
-fn ith_asteroid(i: i32, (station_x, station_y): (i32, i32), input: &str) -> (i32, i32) {
-    let field: BTreeSet<Point> = get_field(input);
-    let angles: Vec<(Point, f64)> = get_angles(&field, (station_x, station_y));
-    println!("{:?}", angles);
-
-    let mut angle_buckets: Vec<Vec<Point>> = Vec::new();
-    let mut last_angle: Option<f64> = None;
-    for (p, angle) in angles {
-        if let Some(last_angle) = last_angle {
-            if (angle - last_angle).abs() < 1e-5 {
-                let i = angle_buckets.len();
-                angle_buckets.get_mut(i).unwrap().push(p);
+/// The asteroids destroyed by a laser that starts pointing up and turns clockwise, destroying
+/// the nearest asteroid in each direction it points in on each rotation.
+struct Sweep {
+    /// The asteroids in each direction, ordered by angle and then nearest first.
+    directions: Vec<VecDeque<Point>>,
+    next: usize,
+}
+
+impl Iterator for Sweep {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        while !self.directions.is_empty() {
+            let i = self.next % self.directions.len();
+            let point = self.directions[i].pop_front();
+            if self.directions[i].is_empty() {
+                self.directions.remove(i);
+                self.next = i;
             } else {
-                angle_buckets.push(vec![p]);
+                self.next = i + 1;
+            }
+            if point.is_some() {
+                return point;
             }
-        } else {
-            angle_buckets.push(vec![p]);
         }
-        last_angle = Some(angle);
+        None
+    }
+}
+
+fn sweep(field: &BTreeSet<Point>, (station_x, station_y): (i32, i32)) -> Sweep {
+    let mut targets: Vec<(Point, Direction, i32)> =
+        field.iter()
+            .filter(|Point { x, y }| *x != station_x || *y != station_y)
+            .map(|p| {
+                let v = Vector { x: p.x - station_x, y: p.y - station_y };
+                (*p, v.direction(), v.x.abs() + v.y.abs())
+            })
+            .collect();
+    targets.sort_by_key(|(_, direction, distance)| (*direction, *distance));
+
+    let mut directions: Vec<VecDeque<Point>> = Vec::new();
+    let mut last_direction: Option<Direction> = None;
+    for (p, direction, _) in targets {
+        match directions.last_mut() {
+            Some(points) if last_direction == Some(direction) => points.push_back(p),
+            _ => directions.push(VecDeque::from(vec![p])),
+        }
+        last_direction = Some(direction);
+    }
+    Sweep { directions, next: 0 }
+}
+
+/// The `i`th asteroid destroyed by the laser, counting from 1.
+fn ith_asteroid(i: usize, station: (i32, i32), input: &str) -> Option<(i32, i32)> {
+    let field: BTreeSet<Point> = get_field(input);
+    sweep(&field, station).nth(i.checked_sub(1)?).map(|p| (p.x, p.y))
+}
+
+/// The width and height of a field, including empty cells.
+fn field_size(input: &str) -> (usize, usize) {
+    let lines: Vec<&str> = input.lines().map(|l| l.trim()).filter(|l| !l.is_empty()).collect();
+    (lines.iter().map(|l| l.len()).max().unwrap_or(0), lines.len())
+}
+
+fn parse_point(s: &str) -> Result<Point, Box<dyn Error>> {
+    match s.split(',').collect::<Vec<_>>()[..] {
+        [x, y] => Ok(Point { x: x.trim().parse()?, y: y.trim().parse()? }),
+        _ => Err(Box::new(SimpleError::new(format!("Invalid point: {}", s)))),
     }
-    println!("{:?}", angle_buckets);
-    let mut destroyed: i32 = 0;
-    let mut i: usize = 0 as usize;
-    (0, 0)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
-    println!("Hello, Day 10.");
+    let mut visible_from = Vec::new();
+    let mut lines_of_sight = Vec::new();
+    let mut ascii_heatmap = false;
+    let mut heatmap_image = None;
+    let mut scale = 8;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--visible" => visible_from.push(parse_point(&args.next().ok_or("--visible needs a point")?)?),
+            "--blockers" => {
+                let from = parse_point(&args.next().ok_or("--blockers needs two points")?)?;
+                let to = parse_point(&args.next().ok_or("--blockers needs two points")?)?;
+                lines_of_sight.push((from, to));
+            }
+            "--heatmap" => ascii_heatmap = true,
+            "--heatmap-image" => heatmap_image = Some(args.next().ok_or("--heatmap-image needs a path")?),
+            "--scale" => scale = args.next().ok_or("--scale needs a value")?.parse()?,
+            _ => return Err(Box::new(SimpleError::new(format!("Unknown argument: {}", arg)))),
+        }
+    }
+
+    let mut input = String::new();
+    std::io::stdin().read_to_string(&mut input)?;
+    let field = get_field(&input);
+
+    let station = best_asteroid(&input)?;
+    let visible = get_angles(&field, station).len();
+    println!("best station: {:?}, seeing {} asteroids", station, visible);
+    match ith_asteroid(200, station, &input) {
+        Some((x, y)) => println!("200th destroyed: {:?}, answer {}", (x, y), x * 100 + y),
+        None => println!("fewer than 200 asteroids to destroy"),
+    }
+
+    for from in visible_from {
+        let visible = visibility::visible_from(&field, from);
+        println!("visible from {},{}: {}", from.x, from.y,
+                 visible.iter().map(|p| format!("{},{}", p.x, p.y)).collect::<Vec<_>>().join(" "));
+    }
+    for (from, to) in lines_of_sight {
+        let blockers = visibility::blockers(&field, from, to);
+        println!("blocking {},{} to {},{}: {}", from.x, from.y, to.x, to.y,
+                 blockers.iter().map(|p| format!("{},{}", p.x, p.y)).collect::<Vec<_>>().join(" "));
+    }
+    if ascii_heatmap || heatmap_image.is_some() {
+        let (width, height) = field_size(&input);
+        let heatmap = Heatmap::new(&field, width, height);
+        if ascii_heatmap {
+            print!("{}", heatmap.to_ascii());
+        }
+        if let Some(path) = heatmap_image {
+            heatmap.to_image(scale).save(&path)?;
+        }
+    }
     Ok(())
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
 
@@ -144,6 +231,14 @@ mod tests {
         let vec1 = Vector { x: 1, y: 1 };
         let vec2 = Vector { x: 4, y: 4 };
         assert_eq!(vec1.eq(&vec2), true);
+
+        let clockwise = [(0, -3), (1, -2), (1, -1), (1, 0), (2, 1), (0, 5), (-1, 1), (-1, 0), (-3, -1)];
+        for pair in clockwise.windows(2) {
+            let vec1 = Vector { x: pair[0].0, y: pair[0].1 };
+            let vec2 = Vector { x: pair[1].0, y: pair[1].1 };
+            assert_eq!(vec1.cmp(&vec2), Ordering::Less);
+            assert_eq!(vec2.cmp(&vec1), Ordering::Greater);
+        }
     }
 
     #[test]
@@ -309,6 +404,44 @@ mod tests {
 ....####...##.#.#...#..#.##.";
         let best_coords = best_asteroid(input).unwrap();
         assert_eq!(best_coords, (22, 19));
-        assert_eq!(ith_asteroid(200, best_coords, input), (22, 19));
+        assert_eq!(ith_asteroid(200, best_coords, input), Some((10, 8)));
+    }
+
+    #[test]
+    fn sweep_example() {
+        let input = ".#..##.###...#######
+                     ##.############..##.
+                     .#.######.########.#
+                     .###.#######.####.#.
+                     #####.##.#.##.###.##
+                     ..#####..#.#########
+                     ####################
+                     #.####....###.#.#.##
+                     ##.#################
+                     #####.##.###..####..
+                     ..######..##.#######
+                     ####.##.####...##..#
+                     .#####..#.######.###
+                     ##...#.##########...
+                     #.##########.#######
+                     .####.#.###.###.#.##
+                     ....##.##.###..#####
+                     .#.#.###########.###
+                     #.#.#.#####.####.###
+                     ###.##.####.##.#..##";
+        let order: Vec<(i32, i32)> = sweep(&get_field(input), (11, 13)).map(|p| (p.x, p.y)).collect();
+        assert_eq!(order[0], (11, 12));
+        assert_eq!(order[1], (12, 1));
+        assert_eq!(order[2], (12, 2));
+        assert_eq!(order[9], (12, 8));
+        assert_eq!(order[19], (16, 0));
+        assert_eq!(order[49], (16, 9));
+        assert_eq!(order[99], (10, 16));
+        assert_eq!(order[198], (9, 6));
+        assert_eq!(order[199], (8, 2));
+        assert_eq!(order[200], (10, 9));
+        assert_eq!(order[298], (11, 1));
+        assert_eq!(order.len(), 299);
+        assert_eq!(ith_asteroid(0, (11, 13), input), None);
     }
 }
\ No newline at end of file
@@ -0,0 +1,136 @@
+use std::collections::{BTreeSet, HashMap};
+use aoc::image::{Color, Image};
+use crate::{Direction, Point, Vector};
+
+/// Darkest to brightest, for drawing heatmaps as text.
+const RAMP: &[u8] = b" .:-=+*#%@";
+
+/// The asteroids that can be seen from `from`, which need not be an asteroid itself, in
+/// clockwise order starting from straight up.
+pub fn visible_from(field: &BTreeSet<Point>, from: Point) -> Vec<Point> {
+    let mut nearest: HashMap<Direction, (i32, Point)> = HashMap::new();
+    for p in field.iter().filter(|p| **p != from) {
+        let v = Vector { x: p.x - from.x, y: p.y - from.y };
+        let distance = v.x.abs() + v.y.abs();
+        let entry = nearest.entry(v.direction()).or_insert((distance, *p));
+        if distance < entry.0 {
+            *entry = (distance, *p);
+        }
+    }
+    let mut visible: Vec<(Direction, Point)> = nearest.into_iter().map(|(d, (_, p))| (d, p)).collect();
+    visible.sort_by_key(|(d, _)| *d);
+    visible.into_iter().map(|(_, p)| p).collect()
+}
+
+/// The asteroids strictly between `from` and `to` that block the line of sight between them,
+/// nearest to `from` first.
+pub fn blockers(field: &BTreeSet<Point>, from: Point, to: Point) -> Vec<Point> {
+    if from == to {
+        return Vec::new();
+    }
+    let step = Vector { x: to.x - from.x, y: to.y - from.y }.direction();
+    (1..)
+        .map(|k| Point { x: from.x + k * step.x(), y: from.y + k * step.y() })
+        .take_while(|p| *p != to)
+        .filter(|p| field.contains(p))
+        .collect()
+}
+
+/// How many asteroids can be seen from every cell of a field.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Heatmap {
+    width: usize,
+    height: usize,
+    counts: Vec<usize>,
+}
+
+impl Heatmap {
+    pub fn new(field: &BTreeSet<Point>, width: usize, height: usize) -> Heatmap {
+        let counts = (0..height)
+            .flat_map(|y| (0..width).map(move |x| Point { x: x as i32, y: y as i32 }))
+            .map(|p| visible_from(field, p).len())
+            .collect();
+        Heatmap { width, height, counts }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> usize {
+        self.counts[y * self.width + x]
+    }
+
+    fn max(&self) -> usize {
+        self.counts.iter().copied().max().unwrap_or(0).max(1)
+    }
+
+    /// Draws one character per cell, brighter where more asteroids can be seen.
+    pub fn to_ascii(&self) -> String {
+        let max = self.max();
+        let mut out = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let level = self.get(x, y) * (RAMP.len() - 1) / max;
+                out.push(RAMP[level] as char);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Draws each cell as a `scale` by `scale` square, going from dark blue where few asteroids
+    /// can be seen to yellow where the most can.
+    pub fn to_image(&self, scale: usize) -> Image {
+        let max = self.max();
+        let mut image = Image::new(self.width, self.height, Color::BLACK);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let heat = (self.get(x, y) * 255 / max) as u8;
+                image.set(x, y, Color(heat, heat, 128 - heat / 2));
+            }
+        }
+        image.scaled(scale.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_field;
+
+    const FIELD: &str = ".#..#
+                         .....
+                         #####
+                         ....#
+                         ...##";
+
+    #[test]
+    fn visible() {
+        let field = get_field(FIELD);
+        assert_eq!(visible_from(&field, Point { x: 3, y: 4 }).len(), 8);
+        assert_eq!(visible_from(&field, Point { x: 1, y: 0 }).len(), 7);
+        // From an empty cell, (3, 2) hides (4, 3).
+        let visible = visible_from(&field, Point { x: 2, y: 1 });
+        assert_eq!(visible[0], Point { x: 4, y: 0 });
+        assert!(visible.contains(&Point { x: 3, y: 2 }));
+        assert!(!visible.contains(&Point { x: 4, y: 3 }));
+    }
+
+    #[test]
+    fn blocked() {
+        let field = get_field(FIELD);
+        assert_eq!(blockers(&field, Point { x: 0, y: 2 }, Point { x: 4, y: 2 }),
+                   vec![Point { x: 1, y: 2 }, Point { x: 2, y: 2 }, Point { x: 3, y: 2 }]);
+        assert_eq!(blockers(&field, Point { x: 1, y: 0 }, Point { x: 3, y: 4 }), vec![Point { x: 2, y: 2 }]);
+        assert_eq!(blockers(&field, Point { x: 4, y: 0 }, Point { x: 4, y: 3 }), vec![Point { x: 4, y: 2 }]);
+        assert!(blockers(&field, Point { x: 3, y: 4 }, Point { x: 4, y: 4 }).is_empty());
+    }
+
+    #[test]
+    fn heatmap() {
+        let field = get_field("#.#");
+        let heatmap = Heatmap::new(&field, 3, 1);
+        assert_eq!(heatmap.counts, vec![1, 2, 1]);
+        assert_eq!(heatmap.to_ascii(), "=@=\n");
+        let image = heatmap.to_image(2);
+        assert_eq!((image.width(), image.height()), (6, 2));
+        assert_eq!(image.get(2, 0), Color(255, 255, 1));
+    }
+}
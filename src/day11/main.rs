@@ -1,122 +1,205 @@
 use std::error::Error;
-use std::io::BufRead;
 use std::collections::{HashMap, HashSet};
-use std::thread;
-use crossbeam_channel::RecvError;
-
-#[derive(Copy, Clone)]
-enum Direction { Left, Right }
-
-#[derive(Copy, Clone)]
-enum Facing { North, South, East, West }
-
-impl Facing {
-    fn mv(&self, (x, y): (i32, i32)) -> (i32, i32) {
-        match *self {
-            Facing::North => (x, y + 1),
-            Facing::South => (x, y - 1),
-            Facing::East => (x + 1, y),
-            Facing::West => (x - 1, y),
-        }
-    }
+use simple_error::SimpleError;
+use aoc::image::{Color, Image};
+use aoc::robot::{Robot, Turn};
 
-    fn turn(&self, direction: Direction) -> Facing {
-        match *self {
-            Facing::North => {
-                match direction {
-                    Direction::Left => Facing::West,
-                    Direction::Right => Facing::East,
-                }
-            }
-            Facing::South => {
-                match direction {
-                    Direction::Left => Facing::East,
-                    Direction::Right => Facing::West,
-                }
-            }
-            Facing::East => {
-                match direction {
-                    Direction::Left => Facing::North,
-                    Direction::Right => Facing::South,
-                }
-            }
-            Facing::West => {
-                match direction {
-                    Direction::Left => Facing::South,
-                    Direction::Right => Facing::North,
+/// How to draw the hull when writing it to an image file.
+struct ImageOptions {
+    path: String,
+    scale: usize,
+    unpainted: Color,
+    black: Color,
+    white: Color,
+    show_path: bool,
+}
+
+const PATH_COLOR: Color = Color(255, 0, 0);
+
+/// Draws every panel the robot visited, with north at the top, optionally tracing its path.
+fn hull_image(hull: &Hull, options: &ImageOptions) -> Image {
+    let (panels, path) = (&hull.panels, &hull.path);
+    let cells = || panels.keys().chain(path.iter());
+    let min_x = cells().map(|(x, _)| *x).min().unwrap_or(0);
+    let max_x = cells().map(|(x, _)| *x).max().unwrap_or(0);
+    let min_y = cells().map(|(_, y)| *y).min().unwrap_or(0);
+    let max_y = cells().map(|(_, y)| *y).max().unwrap_or(0);
+    let to_pixel = |(x, y): (i64, i64)| ((x - min_x) as usize, (max_y - y) as usize);
+
+    let mut image = Image::new((max_x - min_x + 1) as usize, (max_y - min_y + 1) as usize, options.unpainted);
+    for (position, white) in panels {
+        // The starting panel is only in `panels` for its starting colour, which is the unpainted
+        // colour unless the hull starts out white there.
+        let color = match (*white, hull.painted.contains(position)) {
+            (true, _) => options.white,
+            (false, true) => options.black,
+            (false, false) => options.unpainted,
+        };
+        let (x, y) = to_pixel(*position);
+        image.set(x, y, color);
+    }
+    let scale = options.scale.max(1);
+    let mut image = image.scaled(scale);
+    if options.show_path {
+        // Join the centres of consecutive panels, which are always next to each other.
+        let centre = |position| {
+            let (x, y) = to_pixel(position);
+            (x * scale + scale / 2, y * scale + scale / 2)
+        };
+        for (i, position) in path.iter().enumerate() {
+            let (x1, y1) = centre(*position);
+            let (x0, y0) = if i > 0 { centre(path[i - 1]) } else { (x1, y1) };
+            for y in y0.min(y1)..=y0.max(y1) {
+                for x in x0.min(x1)..=x0.max(x1) {
+                    image.set(x, y, PATH_COLOR);
                 }
             }
         }
     }
+    image
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut input = String::new();
-    let stdin = std::io::stdin();
-    let mut stdin_locked = stdin.lock();
-    stdin_locked.read_line(&mut input)?;
-    let memory: Vec<i64> =
-        input.split(',')
-            .map(|i| i.trim().parse().unwrap())
-            .collect();
-
-    let mut panels: HashMap<(i32, i32), bool> = HashMap::new();
-
-    let (input_sender, input_receiver) = crossbeam_channel::unbounded();
-    let (output_sender, output_receiver) = crossbeam_channel::unbounded();
-    let (input_request_sender, input_request_receiver) = crossbeam_channel::unbounded();
-    thread::spawn(move || {
-        let mut computer = aoc::computer::Computer::new(memory, input_receiver, input_request_sender, output_sender);
-        computer.run_to_completion();
-    });
-
-    let mut positions = HashSet::new();
-    let mut position = (0, 0);
-    let mut facing: Facing = Facing::North;
-    input_sender.send(1);
-    loop {
-        let color_to_paint = match output_receiver.recv() {
-            Ok(0) => false,
-            Ok(_) => true,
-            Err(_) => break,
-        };
-        panels.insert(position, color_to_paint);
-        positions.insert(position);
-        let direction_to_turn = match output_receiver.recv()? {
-            0 => Direction::Left,
-            _ => Direction::Right,
-        };
-        facing = facing.turn(direction_to_turn);
-        position = facing.mv(position);
+/// Everything the robot did during one run.
+struct Hull {
+    /// The color of every panel the robot has seen, with white as `true`.
+    panels: HashMap<(i64, i64), bool>,
+    /// The panels the robot painted at least once.
+    painted: HashSet<(i64, i64)>,
+    /// Every position the robot visited, in order.
+    path: Vec<(i64, i64)>,
+}
 
-        let new_color = match panels.get(&position) {
-            Some(color) => *color,
-            None => false,
-        };
-        input_sender.send(match new_color {
-            true => 1,
-            false => 0,
-        });
-        println!("{}", positions.len());
+/// Runs the painting robot from a panel of the given color until its program halts.
+fn paint(memory: Vec<i64>, start_white: bool) -> Result<Hull, Box<dyn Error>> {
+    // Each command is the color to paint followed by the way to turn.
+    let mut robot: Robot<bool> = Robot::new(memory, 2);
+    robot.record(start_white);
+    let mut painted = HashSet::new();
+    let mut input = None;
+    loop {
+        let step = robot.step(input)?;
+        for command in step.frames {
+            robot.record(command[0] != 0);
+            painted.insert(robot.position());
+            robot.turn(if command[1] == 0 { Turn::Left } else { Turn::Right });
+            robot.forward();
+        }
+        if step.halted {
+            return Ok(Hull { panels: robot.grid().clone(), painted, path: robot.path().to_vec() });
+        }
+        input = Some(if *robot.observe().unwrap_or(&false) { 1 } else { 0 });
     }
-    let mut white_panels: HashSet<(i32, i32)> = panels.iter()
-        .filter(|(p, color)| **color == true)
+}
+
+/// Draws the white panels as `█` characters, with north at the top.
+fn render(panels: &HashMap<(i64, i64), bool>) -> String {
+    let white_panels: HashSet<(i64, i64)> = panels.iter()
+        .filter(|(_, white)| **white)
         .map(|(p, _)| *p)
         .collect();
-    let min_x = white_panels.iter().min_by_key(|(x, _)| *x).unwrap().0;
-    let max_x = white_panels.iter().max_by_key(|(x, _)| *x).unwrap().0;
-    let min_y = white_panels.iter().min_by_key(|(_, y)| *y).unwrap().1;
-    let max_y = white_panels.iter().max_by_key(|(_, y)| *y).unwrap().1;
+    let mut out = String::new();
+    if white_panels.is_empty() {
+        return out;
+    }
+    let min_x = white_panels.iter().map(|(x, _)| *x).min().unwrap();
+    let max_x = white_panels.iter().map(|(x, _)| *x).max().unwrap();
+    let min_y = white_panels.iter().map(|(_, y)| *y).min().unwrap();
+    let max_y = white_panels.iter().map(|(_, y)| *y).max().unwrap();
 
     for y in (min_y..=max_y).rev() {
         for x in min_x..=max_x {
-            if white_panels.contains(&(x, y)) {
-                print!("█");
-            } else {
-                print!(" ");
-            }
+            out.push(if white_panels.contains(&(x, y)) { '█' } else { ' ' });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut image_options = None;
+    let mut image_part = 2;
+    let mut scale = 1;
+    let mut unpainted = Color(128, 128, 128);
+    let mut black = Color::BLACK;
+    let mut white = Color::WHITE;
+    let mut show_path = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--image" => image_options = Some(args.next().ok_or("--image needs a path")?),
+            "--image-part" => image_part = args.next().ok_or("--image-part needs a value")?.parse()?,
+            "--scale" => scale = args.next().ok_or("--scale needs a value")?.parse()?,
+            "--unpainted" => unpainted = args.next().ok_or("--unpainted needs a color")?.parse()?,
+            "--black" => black = args.next().ok_or("--black needs a color")?.parse()?,
+            "--white" => white = args.next().ok_or("--white needs a color")?.parse()?,
+            "--path" => show_path = true,
+            _ => return Err(Box::new(SimpleError::new(format!("Unknown argument: {}", arg)))),
         }
-        print!("\n");
+    }
+    let image_options = image_options
+        .map(|path| ImageOptions { path, scale, unpainted, black, white, show_path });
+
+    let stdin = std::io::stdin();
+    let mut stdin_locked = stdin.lock();
+    let memory: Vec<i64> = aoc::program::read(&mut stdin_locked)?;
+
+    let part1 = paint(memory.clone(), false)?;
+    println!("part 1: {} panels painted", part1.painted.len());
+    let part2 = paint(memory, true)?;
+    print!("part 2:\n{}", render(&part2.panels));
+
+    if let Some(options) = image_options {
+        let hull = match image_part {
+            1 => &part1,
+            2 => &part2,
+            _ => return Err(Box::new(SimpleError::new(format!("No part {}", image_part)))),
+        };
+        hull_image(hull, &options).save(&options.path)?;
     }
     Ok(())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Paints two panels white, turning right after each.
+    const ROBOT: [i64; 13] = [3, 100, 104, 1, 104, 1, 3, 100, 104, 1, 104, 1, 99];
+
+    #[test]
+    fn paints() {
+        let hull = paint(ROBOT.to_vec(), false).unwrap();
+        assert_eq!(hull.painted.len(), 2);
+        assert_eq!(hull.path, vec![(0, 0), (1, 0), (1, -1)]);
+    }
+
+    #[test]
+    fn image_leaves_start_unpainted() {
+        let options = ImageOptions {
+            path: String::new(),
+            scale: 1,
+            unpainted: Color(128, 128, 128),
+            black: Color::BLACK,
+            white: Color::WHITE,
+            show_path: false,
+        };
+        // Halts before painting anything.
+        let hull = paint(vec![99], false).unwrap();
+        assert_eq!(hull_image(&hull, &options).get(0, 0), Color(128, 128, 128));
+        let hull = paint(vec![99], true).unwrap();
+        assert_eq!(hull_image(&hull, &options).get(0, 0), Color::WHITE);
+
+        // Paints the starting panel black and turns left.
+        let hull = paint(vec![104, 0, 104, 0, 99], false).unwrap();
+        assert_eq!(hull_image(&hull, &options).get(1, 0), Color::BLACK);
+    }
+
+    #[test]
+    fn renders() {
+        let mut panels = HashMap::new();
+        panels.insert((0, 1), true);
+        panels.insert((1, 0), true);
+        panels.insert((0, 0), false);
+        assert_eq!(render(&panels), "█ \n █\n");
+    }
+}
@@ -1,4 +1,5 @@
 use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
 use simple_error::SimpleError;
 use std::io::BufRead;
@@ -29,18 +30,42 @@ impl Asteroid {
     fn energy(&self) -> i32 {
         self.potential_energy() * self.velocity.energy()
     }
+
+    /// Moves by the asteroid's velocity.
+    fn mv(&mut self) {
+        self.x += self.velocity.x;
+        self.y += self.velocity.y;
+        self.z += self.velocity.z;
+    }
 }
 
 impl FromStr for Asteroid {
     type Err = SimpleError;
 
+    /// Parses either the puzzle's `<x=-1, y=0, z=2>` or plain `-1, 0, 2`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let components: Vec<i32> = s.split(',').map(|s| s.trim().parse().unwrap()).collect();
-        if components.len() == 3 {
-            Ok(Asteroid::new(components[0], components[1], components[2]))
-        } else {
-            Err(SimpleError::new("Must be exactly 3 components"))
+        let s = s.trim();
+        let (labelled, components) = match s.strip_prefix('<') {
+            Some(rest) => (true, rest.strip_suffix('>').ok_or_else(|| SimpleError::new(format!("Missing '>' in {:?}", s)))?),
+            None => (false, s),
+        };
+        let components: Vec<&str> = components.split(',').map(|c| c.trim()).collect();
+        if components.len() != 3 {
+            return Err(SimpleError::new(format!("Must be exactly 3 components: {:?}", s)));
+        }
+        let mut values = [0; 3];
+        for ((value, component), label) in values.iter_mut().zip(components).zip(&["x=", "y=", "z="]) {
+            let number = if labelled {
+                component.strip_prefix(label)
+                    .ok_or_else(|| SimpleError::new(format!("Expected {:?} to start with {:?}", component, label)))?
+                    .trim()
+            } else {
+                component
+            };
+            *value = number.parse()
+                .map_err(|e| SimpleError::new(format!("Invalid component {:?} in {:?}: {}", component, s, e)))?;
         }
+        Ok(Asteroid::new(values[0], values[1], values[2]))
     }
 }
 
@@ -95,16 +120,63 @@ impl System {
                 y: asteroid.velocity.y + y_move,
                 z: asteroid.velocity.z + z_move,
             };
-            new_asteroids.push(
-                Asteroid {
-                    x: asteroid.x + velocity.x,
-                    y: asteroid.y + velocity.y,
-                    z: asteroid.z + velocity.z,
-                    velocity,
-                });
+            let mut asteroid = Asteroid { velocity, ..*asteroid };
+            asteroid.mv();
+            new_asteroids.push(asteroid);
         }
         self.asteroids = new_asteroids;
     }
+
+    /// The system split into its x, y and z axes, which move independently of each other.
+    fn axes(&self) -> [SingleDimensionalSystem; 3] {
+        let axis = |f: fn(&Asteroid) -> (i32, i32)| SingleDimensionalSystem { asteroids: self.asteroids.iter().map(f).collect() };
+        [
+            axis(|a| (a.x, a.velocity.x)),
+            axis(|a| (a.y, a.velocity.y)),
+            axis(|a| (a.z, a.velocity.z)),
+        ]
+    }
+
+    /// How many steps it takes for the whole system to first return to its current state, or
+    /// `None` if that does not fit in a `u128`.
+    ///
+    /// Each step can be undone, so every state lies on a cycle and the system always comes back
+    /// to where it started. The axes do so separately, and the whole system does once they all
+    /// have at the same time.
+    fn period(&self) -> Option<u128> {
+        self.axes().iter().try_fold(1, |period, axis| lcm(period, u128::from(axis.period())))
+    }
+}
+
+/// Prints one `pos=<...>, vel=<...>` line per asteroid, like the puzzle's examples, with each
+/// column padded to line up.
+impl fmt::Display for System {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns: [fn(&Asteroid) -> i32; 6] = [
+            |a| a.x, |a| a.y, |a| a.z,
+            |a| a.velocity.x, |a| a.velocity.y, |a| a.velocity.z,
+        ];
+        let widths: Vec<usize> = columns.iter()
+            .map(|column| self.asteroids.iter().map(|a| column(a).to_string().len()).max().unwrap_or(0).max(2))
+            .collect();
+        for a in &self.asteroids {
+            let cell = |i: usize| format!("{:>width$}", columns[i](a), width = widths[i]);
+            writeln!(f, "pos=<x={}, y={}, z={}>, vel=<x={}, y={}, z={}>",
+                     cell(0), cell(1), cell(2), cell(3), cell(4), cell(5))?;
+        }
+        Ok(())
+    }
+}
+
+fn gcd(a: u128, b: u128) -> u128 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn lcm(a: u128, b: u128) -> Option<u128> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -116,7 +188,7 @@ impl SingleDimensionalSystem {
     fn step(&mut self) {
         let mut new_asteroids = Vec::new();
         for asteroid in &self.asteroids {
-            let mut diff = self.asteroids.iter().fold(0, |acc, other_asteroid| {
+            let diff = self.asteroids.iter().fold(0, |acc, other_asteroid| {
                 match asteroid.0.cmp(&other_asteroid.0) {
                     Ordering::Less => acc + 1,
                     Ordering::Equal => acc,
@@ -127,9 +199,33 @@ impl SingleDimensionalSystem {
         }
         self.asteroids = new_asteroids;
     }
+
+    /// How many steps it takes to first return to the current state.
+    fn period(&self) -> u64 {
+        let mut system = self.clone();
+        let mut steps = 0;
+        loop {
+            system.step();
+            steps += 1;
+            if system == *self {
+                return steps;
+            }
+        }
+    }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
+    let mut steps = 1000;
+    let mut print_after = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--steps" => steps = args.next().ok_or("--steps needs a value")?.parse()?,
+            "--print" => print_after.push(args.next().ok_or("--print needs a step")?.parse::<usize>()?),
+            _ => return Err(Box::new(SimpleError::new(format!("Unknown argument: {}", arg)))),
+        }
+    }
+
     let mut asteroids: Vec<Asteroid> = Vec::new();
     let stdin = std::io::stdin();
     let stdin_lock = stdin.lock();
@@ -140,40 +236,24 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
         asteroids.push(line.parse()?);
     }
-    let original_x_system = SingleDimensionalSystem { asteroids: asteroids.iter().map(|a| (a.x, a.velocity.x)).collect() };
-    let mut x_system = original_x_system.clone();
-    let mut steps_to_x_cycle = 0;
-    loop {
-        x_system.step();
-        steps_to_x_cycle += 1;
-        if x_system == original_x_system {
-            break;
-        }
-    }
-
-    let original_y_system = SingleDimensionalSystem { asteroids: asteroids.iter().map(|a| (a.y, a.velocity.y)).collect() };
-    let mut y_system = original_y_system.clone();
-    let mut steps_to_y_cycle = 0;
-    loop {
-        y_system.step();
-        steps_to_y_cycle += 1;
-        if y_system == original_y_system {
-            break;
+    let mut system = System { asteroids };
+    let period = system.period().ok_or("The system takes too many steps to repeat")?;
+    let mut printer = system.clone();
+    print_after.sort_unstable();
+    let mut done = 0;
+    for step in print_after {
+        for _ in done..step {
+            printer.step();
         }
+        done = step;
+        println!("After {} step{}:\n{}", step, if step == 1 { "" } else { "s" }, printer);
     }
 
-    let original_z_system = SingleDimensionalSystem { asteroids: asteroids.iter().map(|a| (a.z, a.velocity.z)).collect() };
-    let mut z_system = original_z_system.clone();
-    let mut steps_to_z_cycle = 0;
-    loop {
-        z_system.step();
-        steps_to_z_cycle += 1;
-        if z_system == original_z_system {
-            break;
-        }
+    for _ in 0..steps {
+        system.step();
     }
-
-    println!("Cycles for x: {}, y: {}, z: {}", steps_to_x_cycle, steps_to_y_cycle, steps_to_z_cycle);
+    println!("energy after {} steps: {}", steps, system.energy());
+    println!("steps until repeat: {}", period);
     Ok(())
 }
 
@@ -232,9 +312,10 @@ mod tests {
                 Asteroid::new(15, -4, -4),
             ],
         };
-        assert_eq!(system.energy(), 133);
+        // Nothing is moving yet, so there is no kinetic energy.
+        assert_eq!(system.energy(), 0);
         system.step();
-        assert_eq!(system.energy(), 143);
+        assert_eq!(system.energy(), 19 * 3 + 50 * 7 + 34 * 7 + 16 * 7);
     }
 
     #[test]
@@ -252,4 +333,70 @@ mod tests {
         }
         assert_eq!(system.energy(), 179);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn lcm_overflow() {
+        assert_eq!(lcm(4, 6), Some(12));
+        assert_eq!(lcm(18, 28), Some(252));
+        assert_eq!(lcm(u128::MAX, u128::MAX), Some(u128::MAX));
+        assert_eq!(lcm(u128::MAX, 2), None);
+    }
+
+    #[test]
+    fn system_period() {
+        let system = System {
+            asteroids: vec![
+                Asteroid::new(-1, 0, 2),
+                Asteroid::new(2, -10, -7),
+                Asteroid::new(4, -8, 8),
+                Asteroid::new(3, 5, -1),
+            ],
+        };
+        assert_eq!(system.period(), Some(2772));
+
+        let system = System {
+            asteroids: vec![
+                Asteroid::new(-8, -10, 0),
+                Asteroid::new(5, 5, 10),
+                Asteroid::new(2, -7, 3),
+                Asteroid::new(9, -8, -3),
+            ],
+        };
+        assert_eq!(system.period(), Some(4_686_774_924));
+    }
+
+    const EXAMPLE: &str = "<x=-1, y=0, z=2>
+<x=2, y=-10, z=-7>
+<x=4, y=-8, z=8>
+<x=3, y=5, z=-1>";
+
+    #[test]
+    fn asteroid_from_puzzle() {
+        assert_eq!("<x=-1, y=0, z=2>".parse(), Ok(Asteroid::new(-1, 0, 2)));
+        assert_eq!(" <x= 4,y=-8, z=8> ".parse(), Ok(Asteroid::new(4, -8, 8)));
+        assert!("<x=-1, y=0, z=2".parse::<Asteroid>().is_err());
+        assert!("<x=-1, z=0, y=2>".parse::<Asteroid>().is_err());
+        assert!("<x=-1, y=0>".parse::<Asteroid>().is_err());
+        assert!("1, two, 3".parse::<Asteroid>().is_err());
+    }
+
+    #[test]
+    fn system_display() {
+        let mut system = System { asteroids: EXAMPLE.lines().map(|l| l.parse().unwrap()).collect() };
+        assert_eq!(system.to_string(), "\
+pos=<x=-1, y=  0, z= 2>, vel=<x= 0, y= 0, z= 0>
+pos=<x= 2, y=-10, z=-7>, vel=<x= 0, y= 0, z= 0>
+pos=<x= 4, y= -8, z= 8>, vel=<x= 0, y= 0, z= 0>
+pos=<x= 3, y=  5, z=-1>, vel=<x= 0, y= 0, z= 0>
+");
+        for _ in 0..10 {
+            system.step();
+        }
+        assert_eq!(system.to_string(), "\
+pos=<x= 2, y= 1, z=-3>, vel=<x=-3, y=-2, z= 1>
+pos=<x= 1, y=-8, z= 0>, vel=<x=-1, y= 1, z= 3>
+pos=<x= 3, y=-6, z= 1>, vel=<x= 3, y= 2, z=-3>
+pos=<x= 2, y= 0, z= 4>, vel=<x= 1, y=-1, z=-1>
+");
+    }
+}
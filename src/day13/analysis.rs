@@ -0,0 +1,148 @@
+use crate::arcade::{ArcadeState, Pixel};
+
+/// Where the game keeps its screen and the points for each block.
+#[derive(PartialEq, Eq, Debug)]
+pub struct Tables {
+    /// Address of the tile table, stored a row at a time.
+    pub tiles: usize,
+    /// Address of the score table, which has one entry per tile.
+    pub scores: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Tables {
+    fn len(&self) -> usize {
+        self.width * self.height
+    }
+}
+
+/// Finds the tile table by looking for the first screen, a row at a time, in the program.
+///
+/// The score table is assumed to follow it directly. The ball and paddle may be kept elsewhere,
+/// so their cells match either their own tile or an empty one.
+pub fn find_tables(program: &[i64], first_screen: &ArcadeState) -> Option<Tables> {
+    let ((min_x, min_y), (max_x, max_y)) = first_screen.bounding_box()?;
+    let width = (max_x - min_x + 1) as usize;
+    let height = (max_y - min_y + 1) as usize;
+    let len = width * height;
+    let expected: Vec<Pixel> = (min_y..=max_y)
+        .flat_map(|y| (min_x..=max_x).map(move |x| first_screen.tile(x, y)))
+        .collect();
+    (0..=program.len().checked_sub(2 * len)?)
+        .find(|&offset| {
+            expected.iter().zip(&program[offset..offset + len]).all(|(pixel, word)| match pixel {
+                Pixel::Ball | Pixel::Paddle => *word == *pixel as i64 || *word == Pixel::Empty as i64,
+                _ => *word == *pixel as i64,
+            })
+        })
+        .map(|tiles| Tables { tiles, scores: tiles + len, width, height })
+}
+
+/// Maps a tile position to its entry in the score table: `(multiplier * p + increment) mod n`,
+/// where `p` is the position's index a row or a column at a time and `n` the number of tiles.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct ScoreHash {
+    pub multiplier: i64,
+    pub increment: i64,
+    pub column_major: bool,
+}
+
+impl ScoreHash {
+    pub fn index(&self, tables: &Tables, x: usize, y: usize) -> usize {
+        let p = if self.column_major { x * tables.height + y } else { y * tables.width + x };
+        (self.multiplier * p as i64 + self.increment).rem_euclid(tables.len() as i64) as usize
+    }
+}
+
+/// Finds the hashes the program might use to look up scores, from every multiplication by a
+/// constant that is directly followed by the addition of a constant.
+pub fn score_hashes(program: &[i64]) -> Vec<ScoreHash> {
+    let immediate = |address: usize, opcode: i64| -> Option<i64> {
+        let code = *program.get(address)?;
+        if code % 100 != opcode {
+            return None;
+        }
+        match (code / 100 % 10, code / 1000 % 10) {
+            (1, 0) => program.get(address + 1).copied(),
+            (0, 1) => program.get(address + 2).copied(),
+            _ => None,
+        }
+    };
+    let mut hashes = Vec::new();
+    for address in 0..program.len() {
+        if let (Some(multiplier), Some(increment)) = (immediate(address, 2), immediate(address + 4, 1)) {
+            for &column_major in &[true, false] {
+                let hash = ScoreHash { multiplier, increment, column_major };
+                if multiplier > 1 && !hashes.contains(&hash) {
+                    hashes.push(hash);
+                }
+            }
+        }
+    }
+    hashes
+}
+
+/// The score for breaking every block in the tile table.
+pub fn score(program: &[i64], tables: &Tables, hash: &ScoreHash) -> i64 {
+    let mut score = 0;
+    for y in 0..tables.height {
+        for x in 0..tables.width {
+            if program[tables.tiles + y * tables.width + x] == Pixel::Block as i64 {
+                score += program[tables.scores + hash.index(tables, x, y)];
+            }
+        }
+    }
+    score
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A 4x3 screen with two blocks, stored at 10 and followed by its score table, after some
+    // code that computes (5 * p + 3).
+    fn program() -> Vec<i64> {
+        let mut program = vec![1002, 0, 5, 0, 1001, 0, 3, 0, 99, 0];
+        program.extend(&[
+            1, 1, 1, 1,
+            1, 2, 0, 1,
+            1, 0, 2, 1,
+        ]);
+        program.extend(1..=12);
+        program
+    }
+
+    fn first_screen() -> ArcadeState {
+        let mut state = ArcadeState::new();
+        let tiles = &program()[10..22];
+        for (i, tile) in tiles.iter().enumerate() {
+            state.update((i % 4) as i64, (i / 4) as i64, *tile).unwrap();
+        }
+        state.update(2, 1, 4).unwrap();
+        state
+    }
+
+    #[test]
+    fn finds_tables() {
+        let tables = find_tables(&program(), &first_screen()).unwrap();
+        assert_eq!(tables, Tables { tiles: 10, scores: 22, width: 4, height: 3 });
+    }
+
+    #[test]
+    fn finds_hashes() {
+        assert_eq!(score_hashes(&program()), vec![
+            ScoreHash { multiplier: 5, increment: 3, column_major: true },
+            ScoreHash { multiplier: 5, increment: 3, column_major: false },
+        ]);
+    }
+
+    #[test]
+    fn scores() {
+        let program = program();
+        let tables = find_tables(&program, &first_screen()).unwrap();
+        let hash = ScoreHash { multiplier: 5, increment: 3, column_major: false };
+        // The blocks are at p = 5 and p = 10, so their scores are at (28 % 12) and (53 % 12).
+        assert_eq!(score(&program, &tables, &hash), 5 + 6);
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use simple_error::SimpleError;
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Pixel {
+    Empty = 0,
+    Wall = 1,
+    Block = 2,
+    Paddle = 3,
+    Ball = 4,
+}
+
+/// Folds an x coordinate that has travelled past a wall back into `lo..=hi`, as if it had
+/// bounced off the walls on either side.
+fn reflect(x: i64, lo: i64, hi: i64) -> i64 {
+    if hi <= lo {
+        return lo;
+    }
+    let period = 2 * (hi - lo);
+    let offset = (x - lo).rem_euclid(period);
+    if offset <= hi - lo {
+        lo + offset
+    } else {
+        lo + period - offset
+    }
+}
+
+/// Everything the arcade cabinet has drawn so far, built up from its (x, y, tile) outputs.
+#[derive(Clone, Debug, Default)]
+pub struct ArcadeState {
+    tiles: HashMap<(i64, i64), Pixel>,
+    score: i64,
+    ball_history: Vec<(i64, i64)>,
+    paddle: Option<(i64, i64)>,
+}
+
+impl ArcadeState {
+    pub fn new() -> ArcadeState {
+        ArcadeState::default()
+    }
+
+    /// Applies one output triple: either a tile, or the score if the position is (-1, 0).
+    pub fn update(&mut self, x: i64, y: i64, value: i64) -> Result<(), SimpleError> {
+        if x == -1 && y == 0 {
+            self.score = value;
+            return Ok(());
+        }
+        let pixel = match value {
+            0 => Pixel::Empty,
+            1 => Pixel::Wall,
+            2 => Pixel::Block,
+            3 => Pixel::Paddle,
+            4 => Pixel::Ball,
+            tile => return Err(SimpleError::new(format!("Invalid tile: {}", tile))),
+        };
+        match pixel {
+            Pixel::Ball => self.ball_history.push((x, y)),
+            Pixel::Paddle => self.paddle = Some((x, y)),
+            _ if self.paddle == Some((x, y)) => self.paddle = None,
+            _ => {}
+        }
+        self.tiles.insert((x, y), pixel);
+        Ok(())
+    }
+
+    pub fn tiles(&self) -> &HashMap<(i64, i64), Pixel> {
+        &self.tiles
+    }
+
+    pub fn tile(&self, x: i64, y: i64) -> Pixel {
+        *self.tiles.get(&(x, y)).unwrap_or(&Pixel::Empty)
+    }
+
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    /// Every position the ball has been drawn at, oldest first.
+    pub fn ball_history(&self) -> &[(i64, i64)] {
+        &self.ball_history
+    }
+
+    pub fn ball(&self) -> Option<(i64, i64)> {
+        self.ball_history().last().copied()
+    }
+
+    /// How far the ball moved when it was last drawn.
+    pub fn ball_velocity(&self) -> Option<(i64, i64)> {
+        match *self.ball_history() {
+            [.., (last_x, last_y), (x, y)] => Some((x - last_x, y - last_y)),
+            _ => None,
+        }
+    }
+
+    pub fn paddle(&self) -> Option<(i64, i64)> {
+        self.paddle
+    }
+
+    pub fn blocks_remaining(&self) -> usize {
+        self.tiles().values().filter(|pixel| **pixel == Pixel::Block).count()
+    }
+
+    /// The smallest and largest corners of the area drawn so far.
+    pub fn bounding_box(&self) -> Option<((i64, i64), (i64, i64))> {
+        let tiles = self.tiles();
+        let min_x = tiles.keys().map(|(x, _)| *x).min()?;
+        let max_x = tiles.keys().map(|(x, _)| *x).max()?;
+        let min_y = tiles.keys().map(|(_, y)| *y).min()?;
+        let max_y = tiles.keys().map(|(_, y)| *y).max()?;
+        Some(((min_x, min_y), (max_x, max_y)))
+    }
+
+    /// Predicts the column in which the ball will reach the row just above the paddle, assuming
+    /// it keeps its velocity and bounces off the walls at the edges of the screen. Returns `None`
+    /// unless the ball is on its way down.
+    pub fn predicted_intercept(&self) -> Option<i64> {
+        let (ball_x, ball_y) = self.ball()?;
+        let (_, paddle_y) = self.paddle?;
+        let (vx, vy) = self.ball_velocity()?;
+        if vy <= 0 {
+            return None;
+        }
+        let ((min_x, _), (max_x, _)) = self.bounding_box()?;
+        let steps = (paddle_y - 1 - ball_y) / vy;
+        Some(reflect(ball_x + vx * steps, min_x + 1, max_x - 1))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A 9 wide field with walls at x = 0 and x = 8, and the paddle on row 6.
+    pub(crate) fn field(paddle_x: i64) -> ArcadeState {
+        let mut state = ArcadeState::new();
+        for y in 0..8 {
+            state.update(0, y, 1).unwrap();
+            state.update(8, y, 1).unwrap();
+        }
+        state.update(paddle_x, 6, 3).unwrap();
+        state
+    }
+
+    #[test]
+    fn reflects() {
+        assert_eq!(reflect(3, 1, 7), 3);
+        assert_eq!(reflect(9, 1, 7), 5);
+        assert_eq!(reflect(-1, 1, 7), 3);
+        assert_eq!(reflect(14, 1, 7), 2);
+    }
+
+    #[test]
+    fn tiles_and_score() {
+        let mut state = field(4);
+        state.update(3, 1, 2).unwrap();
+        state.update(4, 1, 2).unwrap();
+        state.update(-1, 0, 12).unwrap();
+        assert_eq!(state.tile(3, 1), Pixel::Block);
+        assert_eq!(state.tiles().get(&(3, 1)), Some(&Pixel::Block));
+        assert_eq!(state.blocks_remaining(), 2);
+        assert_eq!(state.score(), 12);
+        assert_eq!(state.bounding_box(), Some(((0, 0), (8, 7))));
+        assert!(state.update(1, 1, 5).is_err());
+
+        state.update(4, 1, 0).unwrap();
+        assert_eq!(state.blocks_remaining(), 1);
+    }
+
+    #[test]
+    fn tracks_ball_and_paddle() {
+        let mut state = field(4);
+        assert_eq!(state.paddle(), Some((4, 6)));
+        state.update(4, 6, 0).unwrap();
+        assert_eq!(state.paddle(), None);
+        state.update(5, 6, 3).unwrap();
+        assert_eq!(state.paddle(), Some((5, 6)));
+
+        state.update(4, 0, 4).unwrap();
+        assert_eq!(state.ball_velocity(), None);
+        state.update(4, 0, 0).unwrap();
+        state.update(5, 1, 4).unwrap();
+        assert_eq!(state.ball(), Some((5, 1)));
+        assert_eq!(state.ball_history(), &[(4, 0), (5, 1)][..]);
+        assert_eq!(state.ball_velocity(), Some((1, 1)));
+    }
+
+    #[test]
+    fn predicts_intercept() {
+        let mut state = field(1);
+        state.update(4, 0, 4).unwrap();
+        state.update(5, 1, 4).unwrap();
+        // Moving down and right from (5, 1), the ball hits the wall at x = 7 and lands at x = 5.
+        assert_eq!(state.predicted_intercept(), Some(5));
+
+        state.update(4, 0, 4).unwrap();
+        assert_eq!(state.predicted_intercept(), None);
+    }
+}
@@ -0,0 +1,46 @@
+use crate::arcade::ArcadeState;
+
+/// Chooses the joystick position that steers the paddle towards where the ball is going to come
+/// down: -1 for left, 0 to stay still and 1 for right.
+pub fn joystick(state: &ArcadeState) -> i64 {
+    let target = match (state.predicted_intercept(), state.ball(), state.ball_velocity()) {
+        (Some(intercept), _, _) => Some(intercept),
+        // On the way up, stay under the ball.
+        (None, Some((x, _)), Some((vx, _))) => Some(x + vx),
+        (None, Some((x, _)), None) => Some(x),
+        (None, None, _) => None,
+    };
+    match (target, state.paddle()) {
+        (Some(target), Some((paddle_x, _))) => (target - paddle_x).signum(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arcade::tests::field;
+
+    /// The test field with the ball moving from `from` to `to`.
+    fn moving_ball(from: (i64, i64), to: (i64, i64), paddle_x: i64) -> ArcadeState {
+        let mut state = field(paddle_x);
+        state.update(from.0, from.1, 4).unwrap();
+        state.update(from.0, from.1, 0).unwrap();
+        state.update(to.0, to.1, 4).unwrap();
+        state
+    }
+
+    #[test]
+    fn steers_to_intercept() {
+        // The ball bounces off the right wall and comes down at x = 5.
+        assert_eq!(joystick(&moving_ball((4, 0), (5, 1), 1)), 1);
+        assert_eq!(joystick(&moving_ball((4, 0), (5, 1), 5)), 0);
+        assert_eq!(joystick(&moving_ball((4, 0), (5, 1), 7)), -1);
+    }
+
+    #[test]
+    fn follows_rising_ball() {
+        assert_eq!(joystick(&moving_ball((4, 4), (3, 3), 4)), -1);
+        assert_eq!(joystick(&moving_ball((3, 3), (2, 2), 1)), 0);
+    }
+}
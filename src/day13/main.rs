@@ -1,45 +1,66 @@
-#[macro_use]
-extern crate crossbeam_channel;
-
 use std::error::Error;
-use std::io::BufRead;
-use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Stdout, Write};
 use std::thread;
-use crossbeam_channel::{RecvError, TryRecvError, Select};
+use std::time::Duration;
+use crossbeam_channel::{Receiver, Select};
 use simple_error::SimpleError;
-use crossbeam_channel::internal::select;
+use aoc::computer::Computer;
+use arcade::ArcadeState;
+use replay::{Event, Replay};
 
-#[derive(PartialEq, Eq, Hash, Copy, Clone)]
-enum Pixel {
-    Empty,
-    Wall,
-    Block,
-    Paddle,
-    Ball,
-}
+mod analysis;
+mod arcade;
+mod autopilot;
+mod manual;
+mod render;
+mod replay;
 
-fn main() -> Result<(), Box<dyn Error>> {
-    let mut input = String::new();
-    let stdin = std::io::stdin();
-    let mut stdin_locked = stdin.lock();
-    stdin_locked.read_line(&mut input)?;
-    let memory: Vec<i64> =
-        input.split(',')
-            .map(|i| i.trim().parse().unwrap())
-            .collect();
+/// Reads the rest of an (x, y, tile) output triple whose x has already been received.
+fn read_tile(
+    x: i64,
+    output_receiver: &Receiver<i64>,
+    state: &mut ArcadeState,
+    replay: &mut Replay) -> Result<(), Box<dyn Error>> {
+    let y = output_receiver.recv()?;
+    let value = output_receiver.recv()?;
+    state.update(x, y, value)?;
+    replay.push(Event::Tile(x, y, value));
+    Ok(())
+}
 
-    let mut panels: HashMap<(i32, i32), bool> = HashMap::new();
+enum Mode {
+    /// Count the blocks on the screen the game draws before it asks for input.
+    Part1,
+    /// Insert two quarters and let the autopilot play until the game ends.
+    FreePlay,
+    /// Insert two quarters and play with the keyboard.
+    Manual,
+    /// Redraw a game recorded with `--record`, read from stdin.
+    Playback,
+    /// Work out the free-play score from the program itself, and check it with the autopilot.
+    Analysis,
+}
 
+/// Runs the game until it halts or, if `first_screen_only`, until it first asks for input.
+/// Returns the state of the game at that point.
+fn play(
+    memory: Vec<i64>,
+    first_screen_only: bool,
+    renderer: &mut Option<render::Renderer<Stdout>>,
+    replay: &mut Replay) -> Result<ArcadeState, Box<dyn Error>> {
+    let quarters = if first_screen_only { memory[0] } else { 2 };
     let (input_sender, input_receiver) = crossbeam_channel::bounded(1);
     let (output_sender, output_receiver) = crossbeam_channel::unbounded();
     let (input_request_sender, input_request_receiver) = crossbeam_channel::bounded(1);
-    thread::spawn(move || {
-        let mut computer = aoc::computer::Computer::new(memory, input_receiver, input_request_sender, output_sender);
-        computer.run_to_completion();
+    let computer = thread::spawn(move || {
+        Computer::new(memory, input_receiver, input_request_sender, output_sender)
+            .with_patch(0, quarters)
+            .run_to_completion()
+            .map_err(|e| e.to_string())
     });
 
-    let mut pixels: HashMap<(i64, i64), Pixel> = HashMap::new();
-    let mut score = 0;
+    let mut state = ArcadeState::new();
     loop {
         let mut sel = Select::new();
         let in_req_op = sel.recv(&input_request_receiver);
@@ -50,8 +71,20 @@ fn main() -> Result<(), Box<dyn Error>> {
                 let msg = oper.recv(&input_request_receiver);
                 match msg {
                     Ok(_) => {
-                        // TODO: get ball and paddle position
-                        input_sender.send(-1);
+                        // The game has finished drawing this frame, but not all of it may have
+                        // been read yet.
+                        while let Ok(x) = output_receiver.try_recv() {
+                            read_tile(x, &output_receiver, &mut state, replay)?;
+                        }
+                        if let Some(renderer) = renderer.as_mut() {
+                            renderer.draw(&state)?;
+                        }
+                        if first_screen_only {
+                            return Ok(state);
+                        }
+                        let joystick = autopilot::joystick(&state);
+                        replay.push(Event::Input(joystick));
+                        input_sender.send(joystick)?;
                     }
                     Err(_) => break,
                 }
@@ -63,45 +96,121 @@ fn main() -> Result<(), Box<dyn Error>> {
                     Ok(x) => x,
                     Err(_) => break,
                 };
-                let y = output_receiver.recv()?;
-                if x == -1 && y == 0 {
-                    score = output_receiver.recv()?;
-                } else {
-                    let pixel = match output_receiver.recv() {
-                        Ok(0) => Pixel::Empty,
-                        Ok(1) => Pixel::Wall,
-                        Ok(2) => Pixel::Block,
-                        Ok(3) => Pixel::Paddle,
-                        Ok(4) => Pixel::Ball,
-                        _ => break,
-                    };
-                    pixels.insert((x, y), pixel);
-                }
+                read_tile(x, &output_receiver, &mut state, replay)?;
             }
             _ => unreachable!(),
         }
     }
 
-//    let mut blocks_count = pixels.iter()
-//        .filter(|(p, pixel)| **pixel == Pixel::Block)
-//        .count();
-    println!("score: {}", score);
-//        .map(|(p, _)| *p)
-//        .collect();
-//    let min_x = white_panels.iter().min_by_key(|(x, _)| *x).unwrap().0;
-//    let max_x = white_panels.iter().max_by_key(|(x, _)| *x).unwrap().0;
-//    let min_y = white_panels.iter().min_by_key(|(_, y)| *y).unwrap().1;
-//    let max_y = white_panels.iter().max_by_key(|(_, y)| *y).unwrap().1;
-//
-//    for y in (min_y..=max_y).rev() {
-//        for x in min_x..=max_x {
-//            if white_panels.contains(&(x, y)) {
-//                print!("█");
-//            } else {
-//                print!(" ");
-//            }
-//        }
-//        print!("\n");
-//    }
+    while let Ok(x) = output_receiver.try_recv() {
+        read_tile(x, &output_receiver, &mut state, replay)?;
+    }
+    if let Some(renderer) = renderer.as_mut() {
+        renderer.draw(&state)?;
+    }
+    computer.join()
+        .map_err(|_| SimpleError::new("Computer thread panicked"))?
+        .map_err(SimpleError::new)?;
+    Ok(state)
+}
+
+fn analyse(
+    memory: Vec<i64>,
+    renderer: &mut Option<render::Renderer<Stdout>>,
+    replay: &mut Replay) -> Result<ArcadeState, Box<dyn Error>> {
+    let first_screen = play(memory.clone(), true, &mut None, &mut Replay::new())?;
+    let tables = analysis::find_tables(&memory, &first_screen)
+        .ok_or("Could not find the first screen in memory")?;
+    println!("tile table: {} ({}x{})", tables.tiles, tables.width, tables.height);
+    println!("score table: {}", tables.scores);
+
+    let hashes = analysis::score_hashes(&memory);
+    if hashes.is_empty() {
+        return Err(Box::new(SimpleError::new("Could not find the score lookup")));
+    }
+    let scores: Vec<i64> = hashes.iter().map(|hash| analysis::score(&memory, &tables, hash)).collect();
+    for (hash, score) in hashes.iter().zip(&scores) {
+        println!("score {} with index ({} * p + {}) mod {}, {}",
+                 score, hash.multiplier, hash.increment, tables.width * tables.height,
+                 if hash.column_major { "column major" } else { "row major" });
+    }
+
+    let played = play(memory, false, renderer, replay)?;
+    if played.blocks_remaining() > 0 {
+        return Err(Box::new(SimpleError::new(format!(
+            "The autopilot left {} blocks, so its score cannot be compared", played.blocks_remaining()))));
+    }
+    match scores.iter().position(|score| *score == played.score()) {
+        Some(i) => println!("the autopilot agrees with lookup #{}", i + 1),
+        None => println!("the autopilot agrees with no lookup"),
+    }
+    Ok(played)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut mode = Mode::FreePlay;
+    let mut render = false;
+    let mut fps = None;
+    let mut tick = Duration::from_millis(200);
+    let mut record = None;
+    let mut from_frame = 0;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mode" => mode = match args.next().ok_or("--mode needs a value")?.as_str() {
+                "part1" => Mode::Part1,
+                "free-play" => Mode::FreePlay,
+                "manual" => Mode::Manual,
+                "analysis" => Mode::Analysis,
+                "playback" => Mode::Playback,
+                other => return Err(Box::new(SimpleError::new(format!("Unknown mode: {}", other)))),
+            },
+            "--render" => render = true,
+            "--tick-ms" => tick = Duration::from_millis(args.next().ok_or("--tick-ms needs a value")?.parse()?),
+            "--record" => record = Some(args.next().ok_or("--record needs a path")?),
+            "--frame" => from_frame = args.next().ok_or("--frame needs a value")?.parse()?,
+            "--fps" => fps = Some(args.next().ok_or("--fps needs a value")?.parse()?),
+            _ => return Err(Box::new(SimpleError::new(format!("Unknown argument: {}", arg)))),
+        }
+    }
+    let mut renderer = if render {
+        Some(render::Renderer::new(std::io::stdout(), fps))
+    } else {
+        None
+    };
+
+    let stdin = std::io::stdin();
+    let program = || aoc::program::read::<i64, _>(&mut stdin.lock());
+    let mut replay = Replay::new();
+    let state = match mode {
+        Mode::Part1 => play(program()?, true, &mut renderer, &mut replay)?,
+        Mode::FreePlay => play(program()?, false, &mut renderer, &mut replay)?,
+        Mode::Manual => {
+            let renderer = renderer.get_or_insert_with(|| render::Renderer::new(std::io::stdout(), fps));
+            manual::play(program()?, renderer, tick, &mut replay)?
+        }
+        Mode::Analysis => analyse(program()?, &mut renderer, &mut replay)?,
+        Mode::Playback => {
+            let renderer = renderer.get_or_insert_with(|| render::Renderer::new(std::io::stdout(), fps.or(Some(30))));
+            Replay::load(stdin.lock())?.play_back(renderer, from_frame)?
+        }
+    };
+    drop(renderer);
+    if let Some(path) = record {
+        let mut file = BufWriter::new(File::create(path)?);
+        replay.save(&mut file)?;
+        file.flush()?;
+    }
+
+    let blocks = state.blocks_remaining();
+    match mode {
+        Mode::Part1 => println!("blocks: {}", blocks),
+        _ => {
+            if blocks > 0 {
+                eprintln!("{} blocks left", blocks);
+            }
+            println!("score: {}", state.score());
+        }
+    }
     Ok(())
-}
\ No newline at end of file
+}
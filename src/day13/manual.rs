@@ -0,0 +1,170 @@
+use std::error::Error;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use simple_error::SimpleError;
+use aoc::computer::Computer;
+use crate::arcade::ArcadeState;
+use crate::read_tile;
+use crate::replay::{Event, Replay};
+use crate::render::Renderer;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum Key {
+    Left,
+    Right,
+    Neutral,
+    Rewind,
+    Quit,
+}
+
+/// Turns the bytes typed on a raw terminal into keys, skipping any that do not mean anything.
+fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x1b if bytes.get(i + 1) == Some(&b'[') && i + 2 < bytes.len() => {
+                match bytes[i + 2] {
+                    b'D' => keys.push(Key::Left),
+                    b'C' => keys.push(Key::Right),
+                    b'A' | b'B' => keys.push(Key::Neutral),
+                    _ => {}
+                }
+                i += 2;
+            }
+            b' ' => keys.push(Key::Neutral),
+            b'r' | 0x7f => keys.push(Key::Rewind),
+            b'q' | 0x03 => keys.push(Key::Quit),
+            _ => {}
+        }
+        i += 1;
+    }
+    keys
+}
+
+fn stty(tty: &File, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = Command::new("stty")
+        .args(args)
+        .stdin(Stdio::from(tty.try_clone()?))
+        .output()?;
+    if !output.status.success() {
+        return Err(Box::new(SimpleError::new(String::from_utf8_lossy(&output.stderr).trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Puts the terminal into raw input mode until dropped, so keys arrive as soon as they are
+/// pressed and are not echoed.
+struct RawTerminal {
+    tty: File,
+    saved: String,
+}
+
+impl RawTerminal {
+    fn enter() -> Result<RawTerminal, Box<dyn Error>> {
+        let tty = File::open("/dev/tty")?;
+        let saved = stty(&tty, &["-g"])?;
+        stty(&tty, &["-icanon", "-echo", "-isig", "min", "1"])?;
+        Ok(RawTerminal { tty, saved })
+    }
+
+    /// Reads keys from the terminal on a separate thread.
+    fn keys(&self) -> Result<Receiver<Key>, Box<dyn Error>> {
+        let mut tty = self.tty.try_clone()?;
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        thread::spawn(move || {
+            let mut buf = [0u8; 16];
+            while let Ok(n) = tty.read(&mut buf) {
+                if n == 0 {
+                    break;
+                }
+                for key in parse_keys(&buf[..n]) {
+                    if sender.send(key).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(receiver)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = stty(&self.tty, &[&self.saved]);
+    }
+}
+
+/// Lets the player steer the paddle with the arrow keys, with two quarters inserted.
+///
+/// Without a key press within `tick` the joystick is left in the middle. Each press of `r` or
+/// backspace rewinds the game by one frame, and `q` quits.
+pub fn play<W: Write>(
+    memory: Vec<i64>,
+    renderer: &mut Renderer<W>,
+    tick: Duration,
+    replay: &mut Replay) -> Result<ArcadeState, Box<dyn Error>> {
+    let terminal = RawTerminal::enter()?;
+    let keys = terminal.keys()?;
+
+    let (input_sender, input_receiver) = crossbeam_channel::unbounded();
+    let (input_request_sender, input_request_receiver) = crossbeam_channel::unbounded();
+    let (output_sender, output_receiver) = crossbeam_channel::unbounded();
+    let mut computer = Computer::new(
+        memory, input_receiver.clone(), input_request_sender.clone(), output_sender.clone())
+        .with_patch(0, 2);
+
+    let mut state = ArcadeState::new();
+    // The state at every frame so far, each waiting for the joystick, with the length of the
+    // replay at that point.
+    let mut snapshots: Vec<(Computer, ArcadeState, usize)> = Vec::new();
+    loop {
+        let halted = computer.run_until_input()?;
+        while let Ok(x) = output_receiver.try_recv() {
+            read_tile(x, &output_receiver, &mut state, replay)?;
+        }
+        while input_request_receiver.try_recv().is_ok() {}
+        renderer.draw(&state)?;
+        if halted {
+            break;
+        }
+        snapshots.push((computer.fork(), state.clone(), replay.len()));
+
+        let joystick = match keys.recv_timeout(tick) {
+            Ok(Key::Left) => -1,
+            Ok(Key::Right) => 1,
+            Ok(Key::Neutral) | Err(RecvTimeoutError::Timeout) => 0,
+            Ok(Key::Rewind) => {
+                let current = snapshots.pop().unwrap();
+                let (snapshot, snapshot_state, replay_len) = snapshots.pop().unwrap_or(current);
+                computer = snapshot.with_io(
+                    input_receiver.clone(), input_request_sender.clone(), output_sender.clone());
+                state = snapshot_state;
+                replay.truncate(replay_len);
+                continue;
+            }
+            Ok(Key::Quit) | Err(RecvTimeoutError::Disconnected) => break,
+        };
+        replay.push(Event::Input(joystick));
+        input_sender.send(joystick)?;
+    }
+    drop(terminal);
+    Ok(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_keys() {
+        assert_eq!(parse_keys(b"\x1b[D\x1b[C \x1b[Ar\x7fxq"), vec![
+            Key::Left, Key::Right, Key::Neutral, Key::Neutral, Key::Rewind, Key::Rewind, Key::Quit,
+        ]);
+        assert_eq!(parse_keys(b"\x1b["), vec![]);
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::arcade::{ArcadeState, Pixel};
+
+const CLEAR_SCREEN: &str = "\x1b[2J";
+const CURSOR_HOME: &str = "\x1b[H";
+const HIDE_CURSOR: &str = "\x1b[?25l";
+const SHOW_CURSOR: &str = "\x1b[?25h";
+const RESET: &str = "\x1b[0m";
+const CLEAR_LINE: &str = "\x1b[K";
+
+fn glyph(pixel: Pixel) -> &'static str {
+    match pixel {
+        Pixel::Empty => " ",
+        Pixel::Wall => "\x1b[37m█",
+        Pixel::Block => "\x1b[33m▒",
+        Pixel::Paddle => "\x1b[36m▀",
+        Pixel::Ball => "\x1b[31m●",
+    }
+}
+
+/// Draws one frame of the arcade screen, followed by the score.
+pub fn frame(state: &ArcadeState) -> String {
+    let mut out = String::new();
+    if let Some(((min_x, min_y), (max_x, max_y))) = state.bounding_box() {
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                out.push_str(glyph(state.tile(x, y)));
+            }
+            out.push_str(RESET);
+            out.push('\n');
+        }
+    }
+    out.push_str(&format!("Score: {}{}\n", state.score(), CLEAR_LINE));
+    out
+}
+
+/// Redraws the arcade screen in place on a terminal, optionally limited to a frame rate.
+pub struct Renderer<W: Write> {
+    out: W,
+    frame_duration: Option<Duration>,
+    last_frame: Option<Instant>,
+}
+
+impl<W: Write> Renderer<W> {
+    pub fn new(out: W, fps: Option<u32>) -> Renderer<W> {
+        Renderer {
+            out,
+            frame_duration: fps.map(|fps| Duration::from_secs(1) / fps.max(1)),
+            last_frame: None,
+        }
+    }
+
+    pub fn draw(&mut self, state: &ArcadeState) -> std::io::Result<()> {
+        match (self.last_frame, self.frame_duration) {
+            (None, _) => write!(self.out, "{}{}", HIDE_CURSOR, CLEAR_SCREEN)?,
+            (Some(last_frame), Some(frame_duration)) => {
+                let elapsed = last_frame.elapsed();
+                if elapsed < frame_duration {
+                    thread::sleep(frame_duration - elapsed);
+                }
+            }
+            _ => {}
+        }
+        self.last_frame = Some(Instant::now());
+        write!(self.out, "{}{}", CURSOR_HOME, frame(state))?;
+        self.out.flush()
+    }
+}
+
+impl<W: Write> Drop for Renderer<W> {
+    fn drop(&mut self) {
+        let _ = write!(self.out, "{}", SHOW_CURSOR);
+        let _ = self.out.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draws_frame() {
+        let mut state = ArcadeState::new();
+        state.update(0, 0, 1).unwrap();
+        state.update(2, 0, 1).unwrap();
+        state.update(1, 1, 4).unwrap();
+        state.update(-1, 0, 12).unwrap();
+        let frame = frame(&state);
+        let lines: Vec<&str> = frame.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], format!("{} {}{}", glyph(Pixel::Wall), glyph(Pixel::Wall), RESET));
+        assert_eq!(lines[1], format!(" {} {}", glyph(Pixel::Ball), RESET));
+        assert_eq!(lines[2], format!("Score: 12{}", CLEAR_LINE));
+    }
+
+    #[test]
+    fn throttles() {
+        let state = ArcadeState::new();
+        let mut renderer = Renderer::new(Vec::new(), Some(50));
+        let start = Instant::now();
+        for _ in 0..3 {
+            renderer.draw(&state).unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+}
@@ -0,0 +1,124 @@
+use std::error::Error;
+use std::io::{BufRead, Write};
+use simple_error::SimpleError;
+use crate::arcade::ArcadeState;
+use crate::render::Renderer;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Event {
+    /// An (x, y, tile) output triple, which may also be a score update.
+    Tile(i64, i64, i64),
+    /// The joystick position sent after a frame was drawn.
+    Input(i64),
+}
+
+/// Everything that happened in one game, in order. Each input ends a frame.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Replay {
+    events: Vec<Event>,
+}
+
+impl Replay {
+    pub fn new() -> Replay {
+        Replay::default()
+    }
+
+    pub fn push(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Forgets everything after the first `len` events, for when a game is rewound.
+    pub fn truncate(&mut self, len: usize) {
+        self.events.truncate(len);
+    }
+
+    /// Writes the replay as one `tile x y value` or `input value` line per event.
+    pub fn save<W: Write>(&self, w: &mut W) -> Result<(), Box<dyn Error>> {
+        for event in &self.events {
+            match event {
+                Event::Tile(x, y, value) => writeln!(w, "tile {} {} {}", x, y, value)?,
+                Event::Input(value) => writeln!(w, "input {}", value)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load<R: BufRead>(r: R) -> Result<Replay, Box<dyn Error>> {
+        let mut events = Vec::new();
+        for (number, line) in r.lines().enumerate() {
+            let line = line?;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let event = match parts[..] {
+                [] => continue,
+                ["tile", x, y, value] => Event::Tile(x.parse()?, y.parse()?, value.parse()?),
+                ["input", value] => Event::Input(value.parse()?),
+                _ => return Err(Box::new(SimpleError::new(
+                    format!("Invalid replay line {}: {}", number + 1, line)))),
+            };
+            events.push(event);
+        }
+        Ok(Replay { events })
+    }
+
+    /// Redraws the game, skipping straight to frame `from_frame`. Returns the final state.
+    pub fn play_back<W: Write>(&self, renderer: &mut Renderer<W>, from_frame: usize) -> Result<ArcadeState, Box<dyn Error>> {
+        let mut state = ArcadeState::new();
+        let mut frame = 0;
+        for event in &self.events {
+            match *event {
+                Event::Tile(x, y, value) => state.update(x, y, value)?,
+                Event::Input(_) => {
+                    if frame >= from_frame {
+                        renderer.draw(&state)?;
+                    }
+                    frame += 1;
+                }
+            }
+        }
+        renderer.draw(&state)?;
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replay() -> Replay {
+        let mut replay = Replay::new();
+        replay.push(Event::Tile(0, 0, 1));
+        replay.push(Event::Tile(1, 0, 4));
+        replay.push(Event::Input(-1));
+        replay.push(Event::Tile(-1, 0, 7));
+        replay.push(Event::Input(0));
+        replay
+    }
+
+    #[test]
+    fn save_and_load() {
+        let mut bytes = Vec::new();
+        replay().save(&mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes.clone()).unwrap(),
+                   "tile 0 0 1\ntile 1 0 4\ninput -1\ntile -1 0 7\ninput 0\n");
+        assert_eq!(Replay::load(&bytes[..]).unwrap(), replay());
+        assert!(Replay::load(&b"tile 1 2\n"[..]).is_err());
+    }
+
+    #[test]
+    fn plays_back() {
+        let mut out = Vec::new();
+        let state = {
+            let mut renderer = Renderer::new(&mut out, None);
+            replay().play_back(&mut renderer, 1).unwrap()
+        };
+        assert_eq!(state.score(), 7);
+        // Frame 0 is skipped, so only frame 1 and the final frame are drawn.
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out.matches("Score: 0").count(), 0);
+        assert_eq!(out.matches("Score: 7").count(), 2);
+    }
+}
@@ -1,28 +1,33 @@
 use std::boxed::Box;
 use std::result::Result;
-use std::io::BufRead;
 use std::error::Error;
+use simple_error::SimpleError;
+use aoc::symbolic;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut input = String::new();
-    std::io::stdin().lock().read_line(&mut input)?;
-    let memory: Vec<usize> =
-        input.split(',')
-            .map(|i| i.trim().parse().unwrap())
-            .collect();
-    for noun in 0..100 {
-        for verb in 0..100 {
-            let mut memory = memory.clone();
-            memory[1] = noun;
-            memory[2] = verb;
-            let mut computer = aoc::computer::Computer::new(memory);
-            let result = computer.run_to_completion()?;
-            if result == 19690720 {
-                println!("noun={}, verb={}", noun, verb);
-                return Ok(())
-            }
+    let mut show_expression = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--expression" => show_expression = true,
+            _ => return Err(Box::new(SimpleError::new(format!("Unknown argument: {}", arg)))),
         }
     }
-    println!("could not find valid inputs");
+
+    let memory: Vec<i64> = aoc::program::read(&mut std::io::stdin().lock())?;
+
+    // memory[0] ends up as an expression in the noun and verb, so solve it for the target rather
+    // than trying every pair.
+    let final_memory = symbolic::execute(&memory, &[1, 2])?;
+    if show_expression {
+        // Shared subexpressions are printed in full each time, so this can get very long.
+        println!("memory[0] = {}", final_memory[0]);
+    }
+    let solutions = symbolic::solve(&final_memory[0], 19690720, &[(1, 0..=99), (2, 0..=99)])?;
+    if solutions.is_empty() {
+        println!("could not find valid inputs");
+    }
+    for solution in solutions {
+        println!("noun={}, verb={}", solution[0], solution[1]);
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -4,17 +4,16 @@ use std::io::BufRead;
 use std::error::Error;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut input = String::new();
     let stdin = std::io::stdin();
     let mut stdin_locked = stdin.lock();
-    stdin_locked.read_line(&mut input)?;
-    let memory: Vec<i32> =
-        input.split(',')
-            .map(|i| i.trim().parse().unwrap())
-            .collect();
-    let mut stdout = std::io::stdout();
+    let memory: Vec<i32> = aoc::program::read(&mut stdin_locked)?;
+    let mut system_id = String::new();
+    stdin_locked.read_line(&mut system_id)?;
+    let system_id: i32 = system_id.trim().parse()?;
 
-    let mut computer = aoc::computer::Computer::new(memory, &mut stdin_locked, &mut stdout);
-    computer.run_to_completion()?;
+    let computer = aoc::computer::Computer::load(memory);
+    for val in computer.outputs(vec![system_id]) {
+        println!("{}", val?);
+    }
     Ok(())
-}
\ No newline at end of file
+}
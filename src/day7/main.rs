@@ -1,18 +1,12 @@
 use std::error::Error;
-use std::io::BufRead;
 use crossbeam_channel::{Sender, Receiver};
 use std::thread;
 use aoc::computer::Computer;
 
 fn main() -> Result<(), Box<dyn Error>> {
-    let mut input = String::new();
     let stdin = std::io::stdin();
     let mut stdin_locked = stdin.lock();
-    stdin_locked.read_line(&mut input)?;
-    let memory: Vec<i32> =
-        input.split(',')
-            .map(|i| i.trim().parse().unwrap())
-            .collect();
+    let memory: Vec<i32> = aoc::program::read(&mut stdin_locked)?;
 
     // Why is there no permutations() function in the standard library?
     // Do the dumb thing for now.
@@ -60,13 +54,15 @@ fn run_pipeline(memory: &Vec<i32>, phases: [i32; 5]) -> Result<(i32), Box<dyn Er
         let rx = chans[i].1.clone();
         let tx = chans[i + 1].0.clone();
         thread::spawn(move || {
-            let mut computer = Computer::new(memory.clone(), rx, tx);
+            let (request_tx, _request_rx) = crossbeam_channel::unbounded();
+            let mut computer = Computer::new(memory.clone(), rx, request_tx, tx);
             computer.run_to_completion().unwrap();
         });
     }
     chans[0].0.send(0);
     chans[4].0.send(phases[4]);
-    let mut computer = Computer::new(memory.clone(), chans[4].1.clone(), chans[0].0.clone());
+    let (request_tx, _request_rx) = crossbeam_channel::unbounded();
+    let mut computer = Computer::new(memory.clone(), chans[4].1.clone(), request_tx, chans[0].0.clone());
     computer.run_to_completion()?;
 
     let val = chans[0].1.recv()?;
@@ -0,0 +1,230 @@
+use std::error::Error;
+use std::io::Write;
+use std::str::FromStr;
+use simple_error::SimpleError;
+use crate::program::crc32;
+
+/// An RGB color.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub struct Color(pub u8, pub u8, pub u8);
+
+impl Color {
+    pub const BLACK: Color = Color(0, 0, 0);
+    pub const WHITE: Color = Color(255, 255, 255);
+
+    /// The perceived brightness of this color, for grayscale output.
+    pub fn luma(self) -> u8 {
+        ((299 * u32::from(self.0) + 587 * u32::from(self.1) + 114 * u32::from(self.2)) / 1000) as u8
+    }
+}
+
+impl FromStr for Color {
+    type Err = SimpleError;
+
+    /// Parses a color written as `rrggbb` in hex, optionally preceded by `#`.
+    fn from_str(s: &str) -> Result<Color, SimpleError> {
+        let hex = s.trim_start_matches('#');
+        let invalid = || SimpleError::new(format!("Invalid color: {}", s));
+        if hex.len() != 6 || !hex.is_ascii() {
+            return Err(invalid());
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| invalid());
+        Ok(Color(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+/// A picture made of colored pixels, stored a row at a time from the top.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize, background: Color) -> Image {
+        Image { width, height, pixels: vec![background; width * height] }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Color {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Color) {
+        self.pixels[y * self.width + x] = color;
+    }
+
+    /// Blows every pixel up into a `factor` by `factor` square.
+    pub fn scaled(&self, factor: usize) -> Image {
+        let mut scaled = Image::new(self.width * factor, self.height * factor, Color::BLACK);
+        for y in 0..scaled.height {
+            for x in 0..scaled.width {
+                scaled.set(x, y, self.get(x / factor, y / factor));
+            }
+        }
+        scaled
+    }
+
+    /// Writes a binary PPM (P6) file.
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> Result<(), Box<dyn Error>> {
+        write!(w, "P6\n{} {}\n255\n", self.width, self.height)?;
+        for color in &self.pixels {
+            w.write_all(&[color.0, color.1, color.2])?;
+        }
+        Ok(())
+    }
+
+    /// Writes a binary PGM (P5) file, converting each pixel to gray.
+    pub fn write_pgm<W: Write>(&self, w: &mut W) -> Result<(), Box<dyn Error>> {
+        write!(w, "P5\n{} {}\n255\n", self.width, self.height)?;
+        let gray: Vec<u8> = self.pixels.iter().map(|color| color.luma()).collect();
+        w.write_all(&gray)?;
+        Ok(())
+    }
+
+    /// Writes an 8-bit RGB PNG file. The image data is stored uncompressed.
+    pub fn write_png<W: Write>(&self, w: &mut W) -> Result<(), Box<dyn Error>> {
+        w.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+        let mut header = Vec::new();
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, default compression and filtering, no interlacing.
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        write_chunk(w, b"IHDR", &header)?;
+
+        let mut raw = Vec::with_capacity(self.height * (1 + 3 * self.width));
+        for row in self.pixels.chunks(self.width.max(1)).take(self.height) {
+            // No filter.
+            raw.push(0);
+            for color in row {
+                raw.extend_from_slice(&[color.0, color.1, color.2]);
+            }
+        }
+        write_chunk(w, b"IDAT", &zlib_stored(&raw))?;
+        write_chunk(w, b"IEND", &[])?;
+        Ok(())
+    }
+
+    /// Writes the image in the format given by the extension of `path`: `ppm`, `pgm` or `png`.
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let extension = path.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+        let mut bytes = Vec::new();
+        match extension.as_str() {
+            "ppm" => self.write_ppm(&mut bytes)?,
+            "pgm" => self.write_pgm(&mut bytes)?,
+            "png" => self.write_png(&mut bytes)?,
+            _ => return Err(Box::new(SimpleError::new(format!("Unknown image format: {}", path)))),
+        }
+        std::fs::write(path, bytes)?;
+        Ok(())
+    }
+}
+
+fn write_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> Result<(), Box<dyn Error>> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut checked = kind.to_vec();
+    checked.extend_from_slice(data);
+    w.write_all(&checked)?;
+    w.write_all(&crc32(&checked).to_be_bytes())?;
+    Ok(())
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + u32::from(*byte)) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+/// Wraps `data` in a zlib stream made of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(0xffff).peekable();
+    if blocks.peek().is_none() {
+        out.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        out.push(if blocks.peek().is_none() { 1 } else { 0 });
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image() -> Image {
+        let mut image = Image::new(2, 1, Color::BLACK);
+        image.set(1, 0, Color(255, 0, 0));
+        image
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!("#ff8000".parse::<Color>().unwrap(), Color(255, 128, 0));
+        assert_eq!("0000ff".parse::<Color>().unwrap(), Color(0, 0, 255));
+        assert!("#ff80".parse::<Color>().is_err());
+        assert!("gg0000".parse::<Color>().is_err());
+        assert_eq!(Color::WHITE.luma(), 255);
+    }
+
+    #[test]
+    fn scales() {
+        let scaled = image().scaled(2);
+        assert_eq!((scaled.width(), scaled.height()), (4, 2));
+        assert_eq!(scaled.get(1, 1), Color::BLACK);
+        assert_eq!(scaled.get(2, 1), Color(255, 0, 0));
+    }
+
+    #[test]
+    fn ppm_and_pgm() {
+        let mut ppm = Vec::new();
+        image().write_ppm(&mut ppm).unwrap();
+        assert_eq!(ppm, b"P6\n2 1\n255\n\x00\x00\x00\xff\x00\x00".to_vec());
+
+        let mut pgm = Vec::new();
+        image().write_pgm(&mut pgm).unwrap();
+        assert_eq!(pgm, b"P5\n2 1\n255\n\x00\x4c".to_vec());
+    }
+
+    #[test]
+    fn adler() {
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn stored_blocks() {
+        let data = vec![7u8; 70000];
+        let zlib = zlib_stored(&data);
+        assert_eq!(zlib.len(), 2 + 2 * 5 + 70000 + 4);
+        assert_eq!(zlib[2..7], [0, 0xff, 0xff, 0, 0][..]);
+        assert_eq!(zlib[7 + 0xffff], 1);
+    }
+
+    #[test]
+    fn png() {
+        let mut png = Vec::new();
+        image().write_png(&mut png).unwrap();
+        assert_eq!(png[..8], b"\x89PNG\r\n\x1a\n"[..]);
+        assert_eq!(png[8..16], b"\x00\x00\x00\x0dIHDR"[..]);
+        assert_eq!(png[16..29], [0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0][..]);
+        assert_eq!(png[29..33], crc32(&png[12..29]).to_be_bytes()[..]);
+        assert_eq!(png[png.len() - 12..], b"\x00\x00\x00\x00IEND\xae\x42\x60\x82"[..]);
+    }
+}
@@ -0,0 +1,10 @@
+use std::error::Error;
+
+/// Reads an Intcode program, in the text or binary format, from stdin and prints an equivalent
+/// Rust program.
+fn main() -> Result<(), Box<dyn Error>> {
+    let memory: Vec<i64> = aoc::program::read(&mut std::io::stdin().lock())?;
+
+    print!("{}", aoc::transpiler::transpile(&memory));
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use std::error::Error;
+use std::io::{BufRead, Write};
+use simple_error::SimpleError;
+
+/// Converts an Intcode program on stdin between the comma-separated text format and the binary
+/// format. The input format is detected automatically and the output is the other one, unless
+/// `--text` or `--binary` is given.
+fn main() -> Result<(), Box<dyn Error>> {
+    let stdin = std::io::stdin();
+    let mut stdin_locked = stdin.lock();
+    let is_binary = stdin_locked.fill_buf()?.starts_with(aoc::program::MAGIC);
+    let to_binary = match std::env::args().nth(1).as_deref() {
+        Some("--text") => false,
+        Some("--binary") => true,
+        Some(arg) => return Err(Box::new(SimpleError::new(format!("Unknown argument: {}", arg)))),
+        None => !is_binary,
+    };
+
+    let program: Vec<i64> = aoc::program::read(&mut stdin_locked)?;
+    let stdout = std::io::stdout();
+    let mut stdout_locked = stdout.lock();
+    if to_binary {
+        stdout_locked.write_all(&aoc::program::encode(&program)?)?;
+    } else {
+        writeln!(stdout_locked, "{}", aoc::program::to_text(&program))?;
+    }
+    Ok(())
+}
@@ -1 +1,11 @@
-pub mod computer;
\ No newline at end of file
+pub mod computer;
+pub mod word;
+pub mod memory;
+pub mod session;
+pub mod symbolic;
+pub mod transpiler;
+pub mod corpus;
+pub mod program;
+pub mod image;
+pub mod robot;
+pub mod asteroids;
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use crate::word::Word;
+
+/// Number of words in each page of a `PagedMemory`.
+pub const PAGE_SIZE: usize = 1024;
+
+/// Copy-on-write memory made up of fixed-size pages.
+///
+/// Cloning only copies the page table; a page is copied the first time either clone writes to
+/// it. Pages that have never been written are not allocated and read as zero, and the page table
+/// only has entries for the pages that exist, so writing far past the program is cheap.
+#[derive(Clone, Debug)]
+pub struct PagedMemory<W> {
+    pages: BTreeMap<usize, Arc<Vec<W>>>,
+    len: usize,
+}
+
+impl<W: Word> PagedMemory<W> {
+    pub fn new(contents: Vec<W>) -> PagedMemory<W> {
+        let len = contents.len();
+        let pages = contents.chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = chunk.to_vec();
+                page.resize(PAGE_SIZE, W::zero());
+                Arc::new(page)
+            })
+            .enumerate()
+            .collect();
+        PagedMemory { pages, len }
+    }
+
+    /// One past the highest address that has been loaded or written.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, address: usize) -> W {
+        match self.pages.get(&(address / PAGE_SIZE)) {
+            Some(page) => page[address % PAGE_SIZE].clone(),
+            None => W::zero(),
+        }
+    }
+
+    pub fn set(&mut self, address: usize, value: W) {
+        let page = self.pages.entry(address / PAGE_SIZE)
+            .or_insert_with(|| Arc::new(vec![W::zero(); PAGE_SIZE]));
+        Arc::make_mut(page)[address % PAGE_SIZE] = value;
+        if address >= self.len {
+            self.len = address + 1;
+        }
+    }
+
+    /// Copies out every word up to `len()`.
+    pub fn to_vec(&self) -> Vec<W> {
+        (0..self.len).map(|address| self.get(address)).collect()
+    }
+
+    /// Number of pages whose storage is shared with another clone of this memory.
+    pub fn shared_pages(&self) -> usize {
+        self.pages.values().filter(|page| Arc::strong_count(page) > 1).count()
+    }
+}
+
+/// Optional restrictions on how a `Computer` may touch its memory.
+///
+/// The default policy allows everything, matching the behaviour of an unrestricted machine.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryPolicy {
+    /// Highest address that may be read or written.
+    pub max_address: Option<usize>,
+    /// Trap writes into the range occupied by the loaded program.
+    pub read_only_code: bool,
+    /// Trap reads of cells beyond the program that have never been written.
+    pub trap_uninitialized_reads: bool,
+}
+
+/// A violation of a `MemoryPolicy`, recording the instruction pointer at which it happened.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MemoryError {
+    AddressOutOfRange { ip: usize, address: usize },
+    WriteToCode { ip: usize, address: usize },
+    UninitializedRead { ip: usize, address: usize },
+}
+
+impl MemoryError {
+    pub fn ip(&self) -> usize {
+        match *self {
+            MemoryError::AddressOutOfRange { ip, .. } => ip,
+            MemoryError::WriteToCode { ip, .. } => ip,
+            MemoryError::UninitializedRead { ip, .. } => ip,
+        }
+    }
+
+    pub fn address(&self) -> usize {
+        match *self {
+            MemoryError::AddressOutOfRange { address, .. } => address,
+            MemoryError::WriteToCode { address, .. } => address,
+            MemoryError::UninitializedRead { address, .. } => address,
+        }
+    }
+}
+
+impl Display for MemoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match *self {
+            MemoryError::AddressOutOfRange { ip, address } =>
+                write!(f, "address {} out of range at ip={}", address, ip),
+            MemoryError::WriteToCode { ip, address } =>
+                write!(f, "write to code at address {} at ip={}", address, ip),
+            MemoryError::UninitializedRead { ip, address } =>
+                write!(f, "read of uninitialized address {} at ip={}", address, ip),
+        }
+    }
+}
+
+impl std::error::Error for MemoryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unwritten_reads_zero() {
+        let memory = PagedMemory::new(vec![1i64, 2, 3]);
+        assert_eq!(memory.get(2), 3);
+        assert_eq!(memory.get(3), 0);
+        assert_eq!(memory.get(10 * PAGE_SIZE), 0);
+        assert_eq!(memory.len(), 3);
+    }
+
+    #[test]
+    fn write_grows() {
+        let mut memory = PagedMemory::new(vec![1i64]);
+        memory.set(3 * PAGE_SIZE + 5, 42);
+        assert_eq!(memory.get(3 * PAGE_SIZE + 5), 42);
+        assert_eq!(memory.len(), 3 * PAGE_SIZE + 6);
+        assert_eq!(memory.to_vec()[..2], vec![1, 0][..]);
+    }
+
+    #[test]
+    fn far_write() {
+        let mut memory = PagedMemory::new(vec![1i64]);
+        memory.set(1 << 40, 42);
+        assert_eq!(memory.get(1 << 40), 42);
+        assert_eq!(memory.get((1 << 40) - 1), 0);
+        assert_eq!(memory.len(), (1 << 40) + 1);
+        assert_eq!(memory.pages.len(), 2);
+    }
+
+    #[test]
+    fn copy_on_write() {
+        let mut original = PagedMemory::new(vec![7i64; 4 * PAGE_SIZE]);
+        let mut fork = original.clone();
+        assert_eq!(fork.shared_pages(), 4);
+
+        fork.set(PAGE_SIZE + 1, 8);
+        assert_eq!(fork.shared_pages(), 3);
+        assert_eq!(original.shared_pages(), 3);
+        assert_eq!(original.get(PAGE_SIZE + 1), 7);
+        assert_eq!(fork.get(PAGE_SIZE + 1), 8);
+
+        original.set(0, 9);
+        assert_eq!(fork.get(0), 7);
+        assert_eq!(original.shared_pages(), 2);
+    }
+}
@@ -0,0 +1,206 @@
+use std::error::Error;
+use std::io::{BufRead, Read};
+use simple_error::SimpleError;
+use crate::word::Word;
+
+/// The first bytes of every binary Intcode program, followed by the format version.
+pub const MAGIC: &[u8; 4] = b"INTC";
+pub const VERSION: u8 = 1;
+
+/// Parses a comma-separated Intcode program.
+pub fn parse<W: Word>(text: &str) -> Result<Vec<W>, Box<dyn Error>> {
+    text.split(',')
+        .map(|i| {
+            let i = i.trim();
+            i.parse().map_err(|_| Box::new(SimpleError::new(format!("Invalid word: {}", i))) as Box<dyn Error>)
+        })
+        .collect()
+}
+
+/// Formats a program as comma-separated text.
+pub fn to_text<W: Word>(program: &[W]) -> String {
+    program.iter().map(|w| w.to_string()).collect::<Vec<_>>().join(",")
+}
+
+/// Encodes a program in the binary format: `MAGIC`, `VERSION`, the number of words as a varint,
+/// each word as a zigzag varint, and finally the little-endian CRC-32 of everything before it.
+///
+/// Fails if a word does not fit in an `i64`.
+pub fn encode<W: Word>(program: &[W]) -> Result<Vec<u8>, SimpleError> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+    write_varint(&mut bytes, program.len() as u64);
+    for word in program {
+        let word = word.to_i64().ok_or_else(|| SimpleError::new(format!("Word too large to encode: {}", word)))?;
+        write_varint(&mut bytes, ((word << 1) ^ (word >> 63)) as u64);
+    }
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+    Ok(bytes)
+}
+
+/// Decodes a program in the binary format written by `encode`.
+pub fn decode<W: Word>(bytes: &[u8]) -> Result<Vec<W>, Box<dyn Error>> {
+    let mut r = bytes;
+    let program = read_binary(&mut r)?;
+    if !r.is_empty() {
+        return Err(Box::new(SimpleError::new(format!("{} trailing bytes after program", r.len()))));
+    }
+    Ok(program)
+}
+
+/// Reads one program from `r`, in either the binary or the text format.
+///
+/// A text program is a single line. Only the program itself is consumed, so anything after it
+/// can still be read from `r`.
+pub fn read<W: Word, R: BufRead>(r: &mut R) -> Result<Vec<W>, Box<dyn Error>> {
+    if r.fill_buf()?.starts_with(MAGIC) {
+        read_binary(r)
+    } else {
+        let mut line = String::new();
+        r.read_line(&mut line)?;
+        parse(&line)
+    }
+}
+
+/// Reads a single byte, feeding it into the running checksum.
+struct ChecksumReader<'a, R> {
+    r: &'a mut R,
+    crc: u32,
+}
+
+impl<'a, R: Read> ChecksumReader<'a, R> {
+    fn byte(&mut self) -> Result<u8, Box<dyn Error>> {
+        let mut buf = [0u8; 1];
+        self.r.read_exact(&mut buf)?;
+        self.crc = crc32_update(self.crc, &buf);
+        Ok(buf[0])
+    }
+
+    fn varint(&mut self) -> Result<u64, Box<dyn Error>> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(Box::new(SimpleError::new("Varint too long")))
+    }
+}
+
+fn read_binary<W: Word, R: Read>(r: &mut R) -> Result<Vec<W>, Box<dyn Error>> {
+    let mut reader = ChecksumReader { r, crc: !0 };
+    let mut magic = [0u8; 4];
+    for b in magic.iter_mut() {
+        *b = reader.byte()?;
+    }
+    if &magic != MAGIC {
+        return Err(Box::new(SimpleError::new("Not a binary Intcode program")));
+    }
+    let version = reader.byte()?;
+    if version != VERSION {
+        return Err(Box::new(SimpleError::new(format!("Unsupported version: {}", version))));
+    }
+    let count = reader.varint()?;
+    let mut program = Vec::new();
+    for _ in 0..count {
+        let zigzag = reader.varint()?;
+        let word = ((zigzag >> 1) as i64) ^ -((zigzag & 1) as i64);
+        program.push(W::from_i64(word).ok_or_else(|| SimpleError::new(format!("Word does not fit: {}", word)))?);
+    }
+    let expected = !reader.crc;
+    let mut checksum = [0u8; 4];
+    reader.r.read_exact(&mut checksum)?;
+    if u32::from_le_bytes(checksum) != expected {
+        return Err(Box::new(SimpleError::new("Checksum mismatch")));
+    }
+    Ok(program)
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn crc32_update(mut crc: u32, bytes: &[u8]) -> u32 {
+    for byte in bytes {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+/// The standard CRC-32 used by zlib and PNG.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    !crc32_update(!0, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufReader;
+
+    const QUINE: [i64; 16] = [109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn round_trip() {
+        let program = vec![0, 1, -1, 63, -64, 64, 1125899906842624, i64::MAX, i64::MIN];
+        let bytes = encode(&program).unwrap();
+        assert_eq!(&bytes[..5], b"INTC\x01");
+        assert_eq!(bytes[5], 9);
+        assert_eq!(bytes[6..9], [0, 2, 1][..]);
+        assert_eq!(decode::<i64>(&bytes).unwrap(), program);
+    }
+
+    #[test]
+    fn smaller_than_text() {
+        let bytes = encode(&QUINE).unwrap();
+        assert!(bytes.len() < to_text(&QUINE).len());
+    }
+
+    #[test]
+    fn corrupt() {
+        let mut bytes = encode(&QUINE).unwrap();
+        bytes[8] ^= 1;
+        assert!(decode::<i64>(&bytes).is_err());
+
+        let bytes = encode(&QUINE).unwrap();
+        assert!(decode::<i64>(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn narrowing() {
+        let bytes = encode(&[1i64 << 40]).unwrap();
+        assert!(decode::<i32>(&bytes).is_err());
+        assert_eq!(decode::<i128>(&bytes).unwrap(), vec![1i128 << 40]);
+    }
+
+    #[test]
+    fn read_leaves_rest() {
+        let mut input = encode(&QUINE).unwrap();
+        input.extend_from_slice(b"5\n");
+        let mut r = BufReader::new(&input[..]);
+        assert_eq!(read::<i64, _>(&mut r).unwrap(), QUINE.to_vec());
+        let mut rest = String::new();
+        r.read_line(&mut rest).unwrap();
+        assert_eq!(rest, "5\n");
+
+        let mut r = BufReader::new(&b"3,0,4,0,99\n5\n"[..]);
+        assert_eq!(read::<i32, _>(&mut r).unwrap(), vec![3, 0, 4, 0, 99]);
+        let mut rest = String::new();
+        r.read_line(&mut rest).unwrap();
+        assert_eq!(rest, "5\n");
+    }
+}
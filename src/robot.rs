@@ -0,0 +1,186 @@
+use std::error::Error;
+use std::collections::HashMap;
+use crossbeam_channel::{Receiver, Sender};
+use simple_error::SimpleError;
+use crate::computer::Computer;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Turn { Left, Right }
+
+/// The direction a robot is facing. North is towards increasing y.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Heading { North, East, South, West }
+
+impl Heading {
+    pub fn turn(self, turn: Turn) -> Heading {
+        match (self, turn) {
+            (Heading::North, Turn::Left) | (Heading::South, Turn::Right) => Heading::West,
+            (Heading::North, Turn::Right) | (Heading::South, Turn::Left) => Heading::East,
+            (Heading::East, Turn::Left) | (Heading::West, Turn::Right) => Heading::North,
+            (Heading::East, Turn::Right) | (Heading::West, Turn::Left) => Heading::South,
+        }
+    }
+
+    /// The position one step from `(x, y)` in this direction.
+    pub fn mv(self, (x, y): (i64, i64)) -> (i64, i64) {
+        match self {
+            Heading::North => (x, y + 1),
+            Heading::South => (x, y - 1),
+            Heading::East => (x + 1, y),
+            Heading::West => (x - 1, y),
+        }
+    }
+}
+
+/// What a robot's program did between two inputs.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Step {
+    /// The outputs, split into commands of the robot's frame length.
+    pub frames: Vec<Vec<i64>>,
+    pub halted: bool,
+}
+
+/// An Intcode program driving a robot around a grid.
+///
+/// The robot keeps track of where it is, which way it is facing and where it has been, along
+/// with whatever it has learned about each cell. Moving it is up to the caller, which decodes the
+/// program's commands from the frames returned by `step`.
+pub struct Robot<T> {
+    computer: Computer,
+    input: Sender<i64>,
+    output: Receiver<i64>,
+    _input_requests: Receiver<()>,
+    frame_len: usize,
+    position: (i64, i64),
+    heading: Heading,
+    path: Vec<(i64, i64)>,
+    grid: HashMap<(i64, i64), T>,
+}
+
+impl<T> Robot<T> {
+    /// Creates a robot at the origin facing north, whose program outputs commands of
+    /// `frame_len` values each.
+    pub fn new(program: Vec<i64>, frame_len: usize) -> Robot<T> {
+        let (input, input_receiver) = crossbeam_channel::unbounded();
+        let (input_request, input_requests) = crossbeam_channel::unbounded();
+        let (output_sender, output) = crossbeam_channel::unbounded();
+        Robot {
+            computer: Computer::new(program, input_receiver, input_request, output_sender),
+            input,
+            output,
+            _input_requests: input_requests,
+            frame_len: frame_len.max(1),
+            position: (0, 0),
+            heading: Heading::North,
+            path: vec![(0, 0)],
+            grid: HashMap::new(),
+        }
+    }
+
+    /// Sends `input`, if any, to the program and runs it until it wants more input or halts.
+    ///
+    /// Fails if the program stops partway through a command.
+    pub fn step(&mut self, input: Option<i64>) -> Result<Step, Box<dyn Error>> {
+        if let Some(input) = input {
+            self.input.send(input)?;
+        }
+        let halted = self.computer.run_until_input()?;
+        let outputs: Vec<i64> = self.output.try_iter().collect();
+        if !outputs.len().is_multiple_of(self.frame_len) {
+            return Err(Box::new(SimpleError::new(format!(
+                "{} outputs do not make up frames of {}", outputs.len(), self.frame_len))));
+        }
+        let frames = outputs.chunks(self.frame_len).map(|frame| frame.to_vec()).collect();
+        Ok(Step { frames, halted })
+    }
+
+    pub fn position(&self) -> (i64, i64) {
+        self.position
+    }
+
+    pub fn heading(&self) -> Heading {
+        self.heading
+    }
+
+    /// Every position the robot has been at, in order, starting with the origin.
+    pub fn path(&self) -> &[(i64, i64)] {
+        &self.path
+    }
+
+    /// Everything recorded about the cells the robot has visited or seen.
+    pub fn grid(&self) -> &HashMap<(i64, i64), T> {
+        &self.grid
+    }
+
+    pub fn turn(&mut self, turn: Turn) {
+        self.heading = self.heading.turn(turn);
+    }
+
+    /// Moves one cell forward.
+    pub fn forward(&mut self) {
+        let heading = self.heading;
+        self.mv(heading);
+    }
+
+    /// Turns to face `heading` and moves one cell that way.
+    pub fn mv(&mut self, heading: Heading) {
+        self.heading = heading;
+        self.position = heading.mv(self.position);
+        self.path.push(self.position);
+    }
+
+    /// What has been recorded about the cell the robot is on.
+    pub fn observe(&self) -> Option<&T> {
+        self.grid.get(&self.position)
+    }
+
+    /// Records something about the cell the robot is on.
+    pub fn record(&mut self, cell: T) {
+        self.grid.insert(self.position, cell);
+    }
+
+    /// Records something about any cell, such as one the robot has only looked at.
+    pub fn record_at(&mut self, position: (i64, i64), cell: T) {
+        self.grid.insert(position, cell);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turns() {
+        let mut heading = Heading::North;
+        for expected in &[Heading::East, Heading::South, Heading::West, Heading::North] {
+            heading = heading.turn(Turn::Right);
+            assert_eq!(heading, *expected);
+        }
+        assert_eq!(Heading::North.turn(Turn::Left), Heading::West);
+        assert_eq!(Heading::West.mv((0, 0)), (-1, 0));
+    }
+
+    #[test]
+    fn steps_and_moves() {
+        // Reads a value, then outputs it and 7 as a frame before halting.
+        let mut robot: Robot<char> = Robot::new(vec![3, 100, 4, 100, 104, 7, 99], 2);
+        assert_eq!(robot.step(None).unwrap(), Step { frames: vec![], halted: false });
+        assert_eq!(robot.step(Some(5)).unwrap(), Step { frames: vec![vec![5, 7]], halted: true });
+
+        robot.record('#');
+        robot.turn(Turn::Right);
+        robot.forward();
+        robot.mv(Heading::North);
+        assert_eq!(robot.position(), (1, 1));
+        assert_eq!(robot.path(), &[(0, 0), (1, 0), (1, 1)][..]);
+        assert_eq!(robot.observe(), None);
+        robot.record_at((1, 0), '.');
+        assert_eq!(robot.grid().len(), 2);
+    }
+
+    #[test]
+    fn partial_frame() {
+        let mut robot: Robot<()> = Robot::new(vec![104, 1, 99], 2);
+        assert!(robot.step(None).is_err());
+    }
+}
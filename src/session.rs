@@ -0,0 +1,63 @@
+use std::error::Error;
+use std::io::{BufRead, Write};
+use simple_error::SimpleError;
+use crate::word::Word;
+
+/// An input value consumed by a `Computer`, together with the number of instructions the
+/// computer had executed when it consumed it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RecordedInput<W> {
+    pub instruction: u64,
+    pub value: W,
+}
+
+/// Every input consumed during a run of a `Computer`.
+///
+/// Replaying a session against the same program reproduces the run exactly, since Intcode
+/// programs have no other source of nondeterminism.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Session<W> {
+    pub inputs: Vec<RecordedInput<W>>,
+}
+
+impl<W: Word> Session<W> {
+    pub fn new() -> Session<W> {
+        Session { inputs: Vec::new() }
+    }
+
+    /// Writes the session as one `instruction value` pair per line.
+    pub fn save<T: Write>(&self, w: &mut T) -> Result<(), Box<dyn Error>> {
+        for input in &self.inputs {
+            writeln!(w, "{} {}", input.instruction, input.value)?;
+        }
+        Ok(())
+    }
+
+    pub fn load<R: BufRead>(r: R) -> Result<Session<W>, Box<dyn Error>> {
+        let mut inputs = Vec::new();
+        for line in r.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let (instruction, value) = match (parts.next(), parts.next(), parts.next()) {
+                (Some(instruction), Some(value), None) => (instruction, value),
+                _ => return Err(Box::new(SimpleError::new(format!("Invalid session line: {}", line)))),
+            };
+            let value = match value.parse() {
+                Ok(value) => value,
+                Err(_) => return Err(Box::new(SimpleError::new(format!("Invalid input value: {}", value)))),
+            };
+            inputs.push(RecordedInput { instruction: instruction.parse()?, value });
+        }
+        Ok(Session { inputs })
+    }
+}
+
+impl<W: Word> Default for Session<W> {
+    fn default() -> Session<W> {
+        Session::new()
+    }
+}
@@ -0,0 +1,289 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+use simple_error::SimpleError;
+
+/// The value of a memory cell in terms of the program's unknown cells.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Expr {
+    Const(i64),
+    /// The initial value of the unknown cell at this address.
+    Var(usize),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mult(Rc<Expr>, Rc<Expr>),
+    /// A value read through an address that depends on the unknowns. Nothing is known about it,
+    /// so it is only harmless if it is overwritten before it is used.
+    Indirect(Rc<Expr>),
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            Expr::Const(x) => write!(f, "{}", x),
+            Expr::Var(address) => write!(f, "[{}]", address),
+            Expr::Add(x, y) => write!(f, "({} + {})", x, y),
+            Expr::Mult(x, y) => write!(f, "({} * {})", x, y),
+            Expr::Indirect(address) => write!(f, "[{}]", address),
+        }
+    }
+}
+
+/// Executes an Add/Mult-only program with the cells at `unknowns` treated as variables, and
+/// returns the final contents of memory.
+///
+/// Only the instructions used by the day 2 gravity assist program are supported. The program
+/// must not jump, do I/O, or execute an instruction that depends on the unknowns.
+pub fn execute(program: &[i64], unknowns: &[usize]) -> Result<Vec<Rc<Expr>>, SimpleError> {
+    let mut memory: Vec<Rc<Expr>> = program.iter().map(|x| Rc::new(Expr::Const(*x))).collect();
+    for &address in unknowns {
+        if address >= memory.len() {
+            return Err(SimpleError::new(format!("Unknown address {} is outside the program", address)));
+        }
+        memory[address] = Rc::new(Expr::Var(address));
+    }
+
+    let mut ip = 0;
+    loop {
+        let code = match memory.get(ip).map(|x| &**x) {
+            Some(Expr::Const(code)) => *code,
+            Some(_) => return Err(SimpleError::new(format!("Symbolic instruction at ip={}", ip))),
+            None => return Err(SimpleError::new(format!("Ran off the end of memory at ip={}", ip))),
+        };
+        let opcode = code % 100;
+        match opcode {
+            1 | 2 => {
+                let x = read(&memory, ip + 1, (code / 100) % 10)?;
+                let y = read(&memory, ip + 2, (code / 1000) % 10)?;
+                let dest = match memory.get(ip + 3).map(|x| &**x) {
+                    Some(Expr::Const(dest)) if *dest >= 0 && (*dest as usize) < memory.len() => *dest as usize,
+                    _ => return Err(SimpleError::new(format!("Invalid destination at ip={}", ip))),
+                };
+                memory[dest] = match opcode {
+                    1 => Rc::new(Expr::Add(x, y)),
+                    _ => Rc::new(Expr::Mult(x, y)),
+                };
+                ip += 4;
+            }
+            99 => return Ok(memory),
+            _ => return Err(SimpleError::new(format!("Unsupported opcode {} at ip={}", opcode, ip))),
+        }
+    }
+}
+
+fn read(memory: &[Rc<Expr>], address: usize, mode: i64) -> Result<Rc<Expr>, SimpleError> {
+    let param = match memory.get(address) {
+        Some(param) => param.clone(),
+        None => return Err(SimpleError::new(format!("Read past the end of memory at {}", address))),
+    };
+    match mode {
+        0 => match *param {
+            Expr::Const(x) if x >= 0 && (x as usize) < memory.len() => Ok(memory[x as usize].clone()),
+            Expr::Const(x) => Err(SimpleError::new(format!("Invalid address: {}", x))),
+            _ => Ok(Rc::new(Expr::Indirect(param))),
+        },
+        1 => Ok(param),
+        _ => Err(SimpleError::new(format!("Unsupported mode {} at {}", mode, address))),
+    }
+}
+
+/// A sum of monomials, each a product of variables (with repetition) and a coefficient.
+type Polynomial = BTreeMap<Vec<usize>, i64>;
+
+/// Expands `expr` into a polynomial. Subexpressions are shared between cells, so each one is only
+/// expanded once and then looked up in `cache`.
+fn expand(expr: &Expr, cache: &mut HashMap<*const Expr, Rc<Polynomial>>) -> Result<Rc<Polynomial>, SimpleError> {
+    if let Some(polynomial) = cache.get(&(expr as *const Expr)) {
+        return Ok(polynomial.clone());
+    }
+    let overflow = || SimpleError::new("Overflow while expanding expression");
+    let mut result = Polynomial::new();
+    match expr {
+        Expr::Const(x) => {
+            result.insert(vec![], *x);
+        }
+        Expr::Var(address) => {
+            result.insert(vec![*address], 1);
+        }
+        Expr::Add(x, y) => {
+            result = (*expand(x, cache)?).clone();
+            for (monomial, coefficient) in expand(y, cache)?.iter() {
+                let sum = result.entry(monomial.clone()).or_insert(0);
+                *sum = sum.checked_add(*coefficient).ok_or_else(overflow)?;
+            }
+        }
+        Expr::Mult(x, y) => {
+            let y = expand(y, cache)?;
+            for (xm, xc) in expand(x, cache)?.iter() {
+                for (ym, yc) in y.iter() {
+                    let mut monomial = xm.clone();
+                    monomial.extend(ym);
+                    monomial.sort();
+                    let product = xc.checked_mul(*yc).ok_or_else(overflow)?;
+                    let sum = result.entry(monomial).or_insert(0);
+                    *sum = sum.checked_add(product).ok_or_else(overflow)?;
+                }
+            }
+        }
+        Expr::Indirect(address) =>
+            return Err(SimpleError::new(format!("Result depends on a symbolic address: {}", address))),
+    }
+    result.retain(|_, coefficient| *coefficient != 0);
+    let result = Rc::new(result);
+    cache.insert(expr, result.clone());
+    Ok(result)
+}
+
+fn evaluate(polynomial: &Polynomial, values: &BTreeMap<usize, i64>) -> Option<i64> {
+    polynomial.iter().try_fold(0i64, |acc, (monomial, coefficient)| {
+        let term = monomial.iter().try_fold(*coefficient, |acc, var| acc.checked_mul(values[var]))?;
+        acc.checked_add(term)
+    })
+}
+
+/// Finds every assignment of the unknowns within their ranges for which `expr` equals `target`.
+///
+/// One unknown that appears only as a plain linear term is solved for directly, and the others
+/// are enumerated. Each solution lists the values in the same order as `unknowns`.
+pub fn solve(expr: &Expr, target: i64, unknowns: &[(usize, RangeInclusive<i64>)])
+             -> Result<Vec<Vec<i64>>, SimpleError> {
+    let polynomial = expand(expr, &mut HashMap::new())?;
+    for monomial in polynomial.keys() {
+        if let Some(var) = monomial.iter().find(|var| unknowns.iter().all(|(address, _)| address != *var)) {
+            return Err(SimpleError::new(format!("Expression depends on [{}], which has no range", var)));
+        }
+    }
+
+    // The variable to solve for must not be multiplied by anything else.
+    let linear = unknowns.iter()
+        .rposition(|(address, _)| {
+            polynomial.keys().all(|monomial| !monomial.contains(address) || monomial.len() == 1)
+        });
+    let (solved, coefficient) = match linear {
+        Some(i) => {
+            let address = unknowns[i].0;
+            (Some(i), polynomial.get(&vec![address]).cloned().unwrap_or(0))
+        }
+        None => (None, 0),
+    };
+    let mut rest = (*polynomial).clone();
+    if let Some(i) = solved {
+        rest.remove(&vec![unknowns[i].0]);
+    }
+
+    let enumerated: Vec<usize> = (0..unknowns.len()).filter(|i| Some(*i) != solved).collect();
+    let mut solutions = Vec::new();
+    let mut values: BTreeMap<usize, i64> = unknowns.iter().map(|(address, range)| (*address, *range.start())).collect();
+    loop {
+        if let Some(value) = evaluate(&rest, &values) {
+            match solved {
+                Some(i) if coefficient != 0 => {
+                    // A remainder that does not fit cannot be made up by any value in range.
+                    let (address, range) = &unknowns[i];
+                    let remainder = target.checked_sub(value);
+                    if let Some(remainder) = remainder.filter(|r| r % coefficient == 0 && range.contains(&(r / coefficient))) {
+                        values.insert(*address, remainder / coefficient);
+                        solutions.push(unknowns.iter().map(|(address, _)| values[address]).collect());
+                    }
+                }
+                Some(i) => {
+                    if value == target {
+                        for x in unknowns[i].1.clone() {
+                            values.insert(unknowns[i].0, x);
+                            solutions.push(unknowns.iter().map(|(address, _)| values[address]).collect());
+                        }
+                    }
+                }
+                None => {
+                    if value == target {
+                        solutions.push(unknowns.iter().map(|(address, _)| values[address]).collect());
+                    }
+                }
+            }
+        }
+
+        // Advance the enumerated unknowns like an odometer, last one fastest.
+        let mut carried = true;
+        for &i in enumerated.iter().rev() {
+            let (address, range) = &unknowns[i];
+            if values[address] < *range.end() {
+                *values.get_mut(address).unwrap() += 1;
+                carried = false;
+                break;
+            }
+            values.insert(*address, *range.start());
+        }
+        if carried {
+            break;
+        }
+    }
+    solutions.sort();
+    Ok(solutions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::Computer;
+
+    // Shaped like the day 2 program: the first instruction reads through the noun and verb, and
+    // its result is discarded. Afterwards memory[0] = 3 * noun + verb + 7.
+    const PROGRAM: [i64; 26] = [
+        1, 0, 0, 3,
+        2, 1, 24, 0,
+        1, 0, 2, 0,
+        1, 0, 25, 0,
+        99, 0, 0, 0, 0, 0, 0, 0,
+        3, 7];
+
+    #[test]
+    fn linear() {
+        let memory = execute(&PROGRAM, &[1, 2]).unwrap();
+        let solutions = solve(&memory[0], 77, &[(1, 0..=99), (2, 0..=99)]).unwrap();
+        assert_eq!(solutions.len(), 24);
+        for solution in solutions {
+            let mut program = PROGRAM.to_vec();
+            program[1] = solution[0];
+            program[2] = solution[1];
+            let mut computer = Computer::load(program);
+            assert_eq!(computer.run_to_completion().unwrap(), 77);
+        }
+    }
+
+    #[test]
+    fn nonlinear() {
+        let program = vec![2, 5, 6, 0, 99, 0, 0];
+        let memory = execute(&program, &[5, 6]).unwrap();
+        assert_eq!(memory[0].to_string(), "([5] * [6])");
+        let solutions = solve(&memory[0], 12, &[(5, 1..=6), (6, 1..=6)]).unwrap();
+        assert_eq!(solutions, vec![vec![2, 6], vec![3, 4], vec![4, 3], vec![6, 2]]);
+    }
+
+    #[test]
+    fn indirect_result() {
+        let memory = execute(&[1, 0, 0, 0, 99], &[1]).unwrap();
+        assert!(solve(&memory[0], 2, &[(1, 0..=4)]).is_err());
+    }
+
+    #[test]
+    fn shared_subexpressions() {
+        // Doubles [117] 28 times, sharing each result twice, then copies it to memory[0].
+        let mut program: Vec<i64> = (0..28).flat_map(|_| vec![1, 117, 117, 117]).collect();
+        program.extend(&[1, 117, 118, 0, 99, 0, 0]);
+        let memory = execute(&program, &[117]).unwrap();
+        assert_eq!(solve(&memory[0], 5 << 28, &[(117, 0..=99)]).unwrap(), vec![vec![5]]);
+    }
+
+    #[test]
+    fn remainder_overflow() {
+        // memory[0] = [5] - 1, so reaching i64::MAX would need a remainder that does not fit.
+        let memory = execute(&[1, 5, 6, 0, 99, 0, -1], &[5]).unwrap();
+        assert!(solve(&memory[0], i64::MAX, &[(5, 0..=99)]).unwrap().is_empty());
+        assert_eq!(solve(&memory[0], 9, &[(5, 0..=99)]).unwrap(), vec![vec![10]]);
+    }
+
+    #[test]
+    fn unsupported_opcode() {
+        assert!(execute(&[3, 0, 99], &[]).is_err());
+    }
+}
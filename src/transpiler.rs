@@ -0,0 +1,591 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write;
+
+/// Support code included in every generated program: the machine state, memory accesses that
+/// notice writes to compiled code, and an interpreter for everything that was not compiled.
+const RUNTIME: &str = r#"
+const NONE: usize = usize::MAX;
+
+struct Machine {
+    ip: usize,
+    rb: i64,
+    memory: Vec<i64>,
+    valid: Vec<bool>,
+}
+
+impl Machine {
+    fn new() -> Machine {
+        Machine { ip: 0, rb: 0, memory: PROGRAM.to_vec(), valid: vec![true; BLOCKS] }
+    }
+
+    fn read(&self, address: i64) -> Result<i64, Box<dyn Error>> {
+        if address < 0 {
+            return Err(format!("Negative address: {}", address).into());
+        }
+        Ok(self.memory.get(address as usize).cloned().unwrap_or(0))
+    }
+
+    /// Returns true if the write changed code belonging to a compiled block, which is then no
+    /// longer run natively.
+    fn write(&mut self, address: i64, value: i64) -> Result<bool, Box<dyn Error>> {
+        if address < 0 {
+            return Err(format!("Negative address: {}", address).into());
+        }
+        let address = address as usize;
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        if self.memory[address] == value {
+            return Ok(false);
+        }
+        self.memory[address] = value;
+        match BLOCK_OF.get(address) {
+            Some(&block) if block != NONE && self.valid[block] => {
+                self.valid[block] = false;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn jump(&mut self, target: i64) -> Result<(), Box<dyn Error>> {
+        if target < 0 {
+            return Err(format!("Jump to negative address: {}", target).into());
+        }
+        self.ip = target as usize;
+        Ok(())
+    }
+
+    fn address(&self, offset: usize, mode: i64) -> Result<i64, Box<dyn Error>> {
+        let raw = self.read((self.ip + offset) as i64)?;
+        match mode {
+            0 => Ok(raw),
+            2 => Ok(self.rb + raw),
+            _ => Err(format!("Invalid mode {} for address at ip={}", mode, self.ip).into()),
+        }
+    }
+
+    fn param(&self, offset: usize, mode: i64) -> Result<i64, Box<dyn Error>> {
+        match mode {
+            1 => self.read((self.ip + offset) as i64),
+            _ => self.read(self.address(offset, mode)?),
+        }
+    }
+
+    /// Interprets a single instruction. Returns true once the program has halted.
+    fn step(&mut self, input: &Receiver<i64>, input_request: &Sender<()>, output: &Sender<i64>)
+            -> Result<bool, Box<dyn Error>> {
+        let code = self.read(self.ip as i64)?;
+        let modes = [(code / 100) % 10, (code / 1000) % 10, (code / 10000) % 10];
+        match code % 100 {
+            1 => {
+                let val = i64::checked_add(self.param(1, modes[0])?, self.param(2, modes[1])?)
+                    .ok_or_else(|| format!("arithmetic overflow at ip={}", self.ip))?;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            2 => {
+                let val = i64::checked_mul(self.param(1, modes[0])?, self.param(2, modes[1])?)
+                    .ok_or_else(|| format!("arithmetic overflow at ip={}", self.ip))?;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            3 => {
+                let dest = self.address(1, modes[0])?;
+                input_request.send(())?;
+                let val = input.recv()?;
+                self.write(dest, val)?;
+                self.ip += 2;
+            }
+            4 => {
+                output.send(self.param(1, modes[0])?)?;
+                self.ip += 2;
+            }
+            5 => {
+                if self.param(1, modes[0])? != 0 {
+                    let target = self.param(2, modes[1])?;
+                    self.jump(target)?;
+                } else {
+                    self.ip += 3;
+                }
+            }
+            6 => {
+                if self.param(1, modes[0])? == 0 {
+                    let target = self.param(2, modes[1])?;
+                    self.jump(target)?;
+                } else {
+                    self.ip += 3;
+                }
+            }
+            7 => {
+                let val = (self.param(1, modes[0])? < self.param(2, modes[1])?) as i64;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            8 => {
+                let val = (self.param(1, modes[0])? == self.param(2, modes[1])?) as i64;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            9 => {
+                self.rb += self.param(1, modes[0])?;
+                self.ip += 2;
+            }
+            99 => return Ok(true),
+            _ => return Err(format!("Invalid opcode {} at ip={}", code, self.ip).into()),
+        }
+        Ok(false)
+    }
+}
+
+/// Runs the program to completion with the same channel-based I/O as `aoc::computer::Computer`.
+pub fn run(input: Receiver<i64>, input_request: Sender<()>, output: Sender<i64>) -> Result<(), Box<dyn Error>> {
+    let mut m = Machine::new();
+    loop {
+        match m.ip {
+"#;
+
+const RUNTIME_END: &str = r#"            _ => {
+                if m.step(&input, &input_request, &output)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Reads the program's input from stdin, one value per line, and prints its output.
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let (input_sender, input_receiver) = crossbeam_channel::unbounded();
+    let (output_sender, output_receiver) = crossbeam_channel::unbounded();
+    let (input_request_sender, input_request_receiver) = crossbeam_channel::unbounded();
+    let handle = thread::spawn(move || {
+        run(input_receiver, input_request_sender, output_sender).map_err(|e| e.to_string())
+    });
+
+    let stdin = std::io::stdin();
+    let mut stdin_locked = stdin.lock();
+    loop {
+        let mut sel = Select::new();
+        let in_req_op = sel.recv(&input_request_receiver);
+        let out_op = sel.recv(&output_receiver);
+        let oper = sel.select();
+        match oper.index() {
+            i if i == in_req_op => {
+                if oper.recv(&input_request_receiver).is_err() {
+                    break;
+                }
+                let mut line = String::new();
+                stdin_locked.read_line(&mut line)?;
+                input_sender.send(line.trim().parse()?)?;
+            }
+            i if i == out_op => {
+                match oper.recv(&output_receiver) {
+                    Ok(val) => println!("{}", val),
+                    Err(_) => break,
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    for val in output_receiver.iter() {
+        println!("{}", val);
+    }
+
+    handle.join().unwrap()?;
+    Ok(())
+}
+"#;
+
+#[derive(Copy, Clone, Debug)]
+enum Operand {
+    Immediate(i64),
+    Position(i64),
+    Relative(i64),
+}
+
+impl Operand {
+    fn value(&self) -> String {
+        match *self {
+            Operand::Immediate(x) => format!("{}", x),
+            Operand::Position(x) => format!("m.read({})?", x),
+            Operand::Relative(x) => format!("m.read(m.rb + {})?", x),
+        }
+    }
+
+    fn address(&self) -> String {
+        match *self {
+            Operand::Immediate(_) => unreachable!("destinations are never immediate"),
+            Operand::Position(x) => format!("{}", x),
+            Operand::Relative(x) => format!("m.rb + {}", x),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Decoded {
+    opcode: i64,
+    operands: Vec<Operand>,
+}
+
+impl Decoded {
+    fn len(&self) -> usize {
+        self.operands.len() + 1
+    }
+
+    /// Whether execution can never continue with the next instruction.
+    fn is_terminator(&self) -> bool {
+        match (self.opcode, self.operands.first()) {
+            (99, _) => true,
+            (5, Some(Operand::Immediate(x))) => *x != 0,
+            (6, Some(Operand::Immediate(x))) => *x == 0,
+            _ => false,
+        }
+    }
+
+    fn jump_target(&self) -> Option<usize> {
+        match (self.opcode, self.operands.get(1)) {
+            (5, Some(Operand::Immediate(x))) | (6, Some(Operand::Immediate(x))) if *x >= 0 => Some(*x as usize),
+            _ => None,
+        }
+    }
+}
+
+/// Decodes the instruction at `address` as it appears in the unmodified program.
+fn decode(program: &[i64], address: usize) -> Option<Decoded> {
+    let code = *program.get(address)?;
+    if code < 0 {
+        return None;
+    }
+    let (count, writes) = match code % 100 {
+        1 | 2 | 7 | 8 => (3, true),
+        3 => (1, true),
+        4 | 9 => (1, false),
+        5 | 6 => (2, false),
+        99 => (0, false),
+        _ => return None,
+    };
+    let mut modes = code / 100;
+    let mut operands = Vec::new();
+    for i in 0..count {
+        let raw = *program.get(address + 1 + i)?;
+        let operand = match modes % 10 {
+            0 => Operand::Position(raw),
+            1 if !(writes && i == count - 1) => Operand::Immediate(raw),
+            2 => Operand::Relative(raw),
+            _ => return None,
+        };
+        operands.push(operand);
+        modes /= 10;
+    }
+    if modes != 0 {
+        return None;
+    }
+    Some(Decoded { opcode: code % 100, operands })
+}
+
+/// A straight-line run of instructions starting at a jump target.
+struct Block {
+    start: usize,
+    instructions: Vec<(usize, Decoded)>,
+    /// Where execution continues if the block does not end with a jump or halt.
+    next: usize,
+}
+
+fn find_blocks(program: &[i64]) -> Vec<Block> {
+    // Jump targets that can be found without running the program. Anything reached through a
+    // computed jump is left to the interpreter.
+    let mut leaders = BTreeSet::new();
+    let mut seen = BTreeSet::new();
+    let mut worklist = vec![0];
+    while let Some(address) = worklist.pop() {
+        if !seen.insert(address) {
+            continue;
+        }
+        if let Some(instruction) = decode(program, address) {
+            if let Some(target) = instruction.jump_target() {
+                leaders.insert(target);
+                worklist.push(target);
+            }
+            if !instruction.is_terminator() {
+                worklist.push(address + instruction.len());
+            }
+        }
+    }
+    leaders.insert(0);
+
+    let mut claimed: HashMap<usize, usize> = HashMap::new();
+    let mut blocks = Vec::new();
+    for &start in &leaders {
+        let mut instructions = Vec::new();
+        let mut address = start;
+        while let Some(instruction) = decode(program, address) {
+            if (address..address + instruction.len()).any(|a| claimed.contains_key(&a)) {
+                break;
+            }
+            let len = instruction.len();
+            let terminator = instruction.is_terminator();
+            instructions.push((address, instruction));
+            address += len;
+            if terminator || leaders.contains(&address) {
+                break;
+            }
+        }
+        if instructions.is_empty() {
+            continue;
+        }
+        for (a, instruction) in &instructions {
+            for covered in *a..*a + instruction.len() {
+                claimed.insert(covered, blocks.len());
+            }
+        }
+        blocks.push(Block { start, instructions, next: address });
+    }
+    blocks
+}
+
+fn compile_instruction(out: &mut String, address: usize, instruction: &Decoded) {
+    let next = address + instruction.len();
+    let ops = &instruction.operands;
+    // A write to compiled code may have changed the rest of this block, so leave it to the
+    // interpreter from the next instruction on.
+    let write = |dest: &Operand| format!("if m.write({}, val)? {{ m.ip = {}; continue; }}", dest.address(), next);
+    // Overflow is an error, with the same message as `Computer` gives.
+    let checked = |op: &str| format!(
+        "let val = i64::checked_{}({}, {}).ok_or(\"arithmetic overflow at ip={}\")?;",
+        op, ops[0].value(), ops[1].value(), address);
+    match instruction.opcode {
+        1 => {
+            writeln!(out, "                {}", checked("add")).unwrap();
+            writeln!(out, "                {}", write(&ops[2])).unwrap();
+        }
+        2 => {
+            writeln!(out, "                {}", checked("mul")).unwrap();
+            writeln!(out, "                {}", write(&ops[2])).unwrap();
+        }
+        3 => {
+            writeln!(out, "                input_request.send(())?;").unwrap();
+            writeln!(out, "                let val = input.recv()?;").unwrap();
+            writeln!(out, "                {}", write(&ops[0])).unwrap();
+        }
+        4 => {
+            writeln!(out, "                output.send({})?;", ops[0].value()).unwrap();
+        }
+        5 | 6 => {
+            let test = if instruction.opcode == 5 { "!=" } else { "==" };
+            let jump = match ops[1] {
+                Operand::Immediate(target) => format!("m.ip = {};", target),
+                target => format!("m.jump({})?;", target.value()),
+            };
+            if instruction.is_terminator() {
+                writeln!(out, "                {}", jump).unwrap();
+                writeln!(out, "                continue;").unwrap();
+            } else {
+                writeln!(out, "                if {} {} 0 {{ {} continue; }}", ops[0].value(), test, jump).unwrap();
+            }
+        }
+        7 => {
+            writeln!(out, "                let val = ({} < {}) as i64;", ops[0].value(), ops[1].value()).unwrap();
+            writeln!(out, "                {}", write(&ops[2])).unwrap();
+        }
+        8 => {
+            writeln!(out, "                let val = ({} == {}) as i64;", ops[0].value(), ops[1].value()).unwrap();
+            writeln!(out, "                {}", write(&ops[2])).unwrap();
+        }
+        9 => {
+            writeln!(out, "                m.rb += {};", ops[0].value()).unwrap();
+        }
+        99 => {
+            writeln!(out, "                return Ok(());").unwrap();
+        }
+        _ => unreachable!("decode only accepts valid opcodes"),
+    }
+}
+
+fn write_array(out: &mut String, name: &str, ty: &str, values: &[String]) {
+    writeln!(out, "const {}: [{}; {}] = [", name, ty, values.len()).unwrap();
+    for line in values.chunks(16) {
+        writeln!(out, "    {},", line.join(", ")).unwrap();
+    }
+    writeln!(out, "];").unwrap();
+}
+
+/// Translates an Intcode program into the source of a standalone Rust program.
+///
+/// Straight-line code reachable from the start of the program or through constant jumps is
+/// compiled to native code. Computed jumps land in the embedded interpreter, which also takes
+/// over any compiled block once the program writes to it. The generated code depends only on
+/// `std` and `crossbeam_channel`, and exposes a `run` function taking the same channels as
+/// `Computer::new`.
+pub fn transpile(program: &[i64]) -> String {
+    let blocks = find_blocks(program);
+    let mut block_of = vec!["NONE".to_string(); program.len()];
+    for (i, block) in blocks.iter().enumerate() {
+        for (address, instruction) in &block.instructions {
+            for covered in &mut block_of[*address..*address + instruction.len()] {
+                *covered = i.to_string();
+            }
+        }
+    }
+
+    let mut out = String::new();
+    writeln!(out, "// Generated by intcode2rust. Do not edit.").unwrap();
+    writeln!(out).unwrap();
+    writeln!(out, "use std::error::Error;").unwrap();
+    writeln!(out, "use std::io::BufRead;").unwrap();
+    writeln!(out, "use std::thread;").unwrap();
+    writeln!(out, "use crossbeam_channel::{{Receiver, Sender, Select}};").unwrap();
+    writeln!(out).unwrap();
+    let words: Vec<String> = program.iter().map(|x| x.to_string()).collect();
+    write_array(&mut out, "PROGRAM", "i64", &words);
+    writeln!(out).unwrap();
+    writeln!(out, "/// The compiled block that each address of the program belongs to.").unwrap();
+    write_array(&mut out, "BLOCK_OF", "usize", &block_of);
+    writeln!(out).unwrap();
+    writeln!(out, "const BLOCKS: usize = {};", blocks.len()).unwrap();
+    out.push_str(RUNTIME);
+    for (i, block) in blocks.iter().enumerate() {
+        writeln!(out, "            {} if m.valid[{}] => {{", block.start, i).unwrap();
+        for (address, instruction) in &block.instructions {
+            writeln!(out, "                // {}: {:?}", address, &program[*address..*address + instruction.len()]).unwrap();
+            compile_instruction(&mut out, *address, instruction);
+        }
+        if !block.instructions.last().unwrap().1.is_terminator() {
+            writeln!(out, "                m.ip = {};", block.next).unwrap();
+        }
+        writeln!(out, "            }}").unwrap();
+    }
+    out.push_str(RUNTIME_END);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::computer::Computer;
+
+    // The day 9 quine, and a program that calls a function through a computed return address and
+    // modifies its own code.
+    const QUINE: [i64; 16] = [109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99];
+    const CALL_RETURN: [i64; 37] = [
+        109, 200, 3, 100, 1101, 7, 0, 9, 104, 0, 21101, 17, 0, 0, 1105, 1, 30, 4, 100, 99,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+        1002, 100, 2, 100, 2106, 0, 0];
+    // Loops over its input, dispatching each iteration through a table of three cases with a
+    // computed jump. The loop patches its own table lookup, and the second case patches an
+    // immediate in the third, so both of their compiled blocks fall back to the interpreter.
+    const JUMP_TABLE: [i64; 91] = [
+        1106, 1, 29, 1106, 1, 38, 1106, 1, 51, 3, 82, 8, 83, 82, 85, 1005, 85, 79, 101, 88,
+        84, 23, 1001, 0, 0, 86, 105, 1, 86, 1002, 83, 2, 87, 4, 87, 1105, 1, 57, 1001, 83,
+        1000, 87, 4, 87, 1001, 83, 0, 53, 1105, 1, 57, 1001, 83, 7, 87, 4, 87, 1001, 84, 1,
+        84, 1008, 84, 3, 85, 1006, 85, 72, 1101, 0, 0, 84, 1001, 83, 1, 83, 1105, 1, 11, 4,
+        83, 99, 0, 0, 0, 0, 0, 0, 29, 38, 51];
+    // Overflows in a compiled addition if its input is zero, and otherwise jumps through a
+    // computed address to a multiplication that is only ever interpreted and overflows there.
+    const OVERFLOW: [i64; 24] = [
+        3, 20, 5, 20, 21, 1101, i64::MAX, 1, 22, 4, 22, 99, 1002, 23, 2, 22, 4, 22, 99, 0,
+        0, 12, 0, i64::MAX];
+
+    #[allow(dead_code, unreachable_code, clippy::all)]
+    mod quine {
+        include!("../testdata/transpiled/quine.rs");
+    }
+
+    #[allow(dead_code, unreachable_code, clippy::all)]
+    mod call_return {
+        include!("../testdata/transpiled/call_return.rs");
+    }
+
+    #[allow(dead_code, unreachable_code, clippy::all)]
+    mod jump_table {
+        include!("../testdata/transpiled/jump_table.rs");
+    }
+
+    #[allow(dead_code, unreachable_code, clippy::all)]
+    mod overflow {
+        include!("../testdata/transpiled/overflow.rs");
+    }
+
+    /// The `run` function of a generated program.
+    type Run = fn(crossbeam_channel::Receiver<i64>, crossbeam_channel::Sender<()>, crossbeam_channel::Sender<i64>)
+        -> Result<(), Box<dyn std::error::Error>>;
+
+    fn run_transpiled(run: Run, inputs: Vec<i64>) -> Vec<i64> {
+        let (input_sender, input) = crossbeam_channel::unbounded();
+        let (output, output_receiver) = crossbeam_channel::unbounded();
+        let (input_request, _input_requests) = crossbeam_channel::unbounded();
+        for val in inputs {
+            input_sender.send(val).unwrap();
+        }
+        run(input, input_request, output).unwrap();
+        output_receiver.try_iter().collect()
+    }
+
+    /// Runs a generated program that is expected to fail, and returns its error.
+    fn transpiled_error(run: Run, inputs: Vec<i64>) -> String {
+        let (input_sender, input) = crossbeam_channel::unbounded();
+        let (output, _outputs) = crossbeam_channel::unbounded();
+        let (input_request, _input_requests) = crossbeam_channel::unbounded();
+        for val in inputs {
+            input_sender.send(val).unwrap();
+        }
+        run(input, input_request, output).unwrap_err().to_string()
+    }
+
+    fn computer_error(program: &[i64], inputs: Vec<i64>) -> String {
+        Computer::load(program.to_vec()).outputs(inputs).find_map(|output| output.err()).unwrap().to_string()
+    }
+
+    fn run_computer(program: &[i64], inputs: Vec<i64>) -> Vec<i64> {
+        Computer::load(program.to_vec()).outputs(inputs).collect::<Result<_, _>>().unwrap()
+    }
+
+    #[test]
+    fn generated_sources_are_current() {
+        assert_eq!(transpile(&QUINE), include_str!("../testdata/transpiled/quine.rs"));
+        assert_eq!(transpile(&CALL_RETURN), include_str!("../testdata/transpiled/call_return.rs"));
+        assert_eq!(transpile(&JUMP_TABLE), include_str!("../testdata/transpiled/jump_table.rs"));
+        assert_eq!(transpile(&OVERFLOW), include_str!("../testdata/transpiled/overflow.rs"));
+    }
+
+    #[test]
+    fn quine_matches_computer() {
+        assert_eq!(run_transpiled(quine::run, vec![]), run_computer(&QUINE, vec![]));
+        assert_eq!(run_transpiled(quine::run, vec![]), QUINE.to_vec());
+    }
+
+    #[test]
+    fn call_return_matches_computer() {
+        assert_eq!(run_transpiled(call_return::run, vec![21]), run_computer(&CALL_RETURN, vec![21]));
+        assert_eq!(run_transpiled(call_return::run, vec![21]), vec![7, 42]);
+    }
+
+    #[test]
+    fn jump_table_matches_computer() {
+        for n in [0, 1, 7, 50] {
+            assert_eq!(run_transpiled(jump_table::run, vec![n]), run_computer(&JUMP_TABLE, vec![n]));
+        }
+        assert_eq!(run_transpiled(jump_table::run, vec![7]), vec![0, 1001, 3, 6, 1004, 9, 12, 7]);
+    }
+
+    #[test]
+    fn overflow_matches_computer() {
+        for (input, expected) in [(0, "arithmetic overflow at ip=5"), (1, "arithmetic overflow at ip=12")] {
+            assert_eq!(transpiled_error(overflow::run, vec![input]), expected);
+            assert_eq!(computer_error(&OVERFLOW, vec![input]), expected);
+        }
+    }
+
+    #[test]
+    fn blocks() {
+        let blocks = find_blocks(&CALL_RETURN);
+        let starts: Vec<usize> = blocks.iter().map(|b| b.start).collect();
+        assert_eq!(starts, vec![0, 30]);
+        assert_eq!(blocks[0].instructions.len(), 6);
+        assert_eq!(blocks[1].instructions.len(), 2);
+    }
+}
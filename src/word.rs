@@ -0,0 +1,104 @@
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use std::convert::TryFrom;
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+/// A value that can be stored in a single Intcode memory cell.
+///
+/// Opcodes, parameter modes and addresses are always small, so they only need to be converted
+/// to and from `i64`. Arithmetic and comparisons happen at the full width of the word, and
+/// arithmetic that does not fit is reported rather than wrapped.
+pub trait Word: Clone + Debug + Display + FromStr + Ord + From<i32> + Send + 'static {
+    /// Returns this word as an `i64`, or `None` if it does not fit.
+    fn to_i64(&self) -> Option<i64>;
+
+    /// Converts an `i64` to a word, or returns `None` if it does not fit.
+    fn from_i64(x: i64) -> Option<Self>;
+
+    /// Adds two words, or returns `None` if the sum does not fit.
+    fn checked_add(&self, other: &Self) -> Option<Self>;
+
+    /// Multiplies two words, or returns `None` if the product does not fit.
+    fn checked_mul(&self, other: &Self) -> Option<Self>;
+
+    fn zero() -> Self {
+        Self::from(0)
+    }
+
+    fn one() -> Self {
+        Self::from(1)
+    }
+}
+
+impl Word for i32 {
+    fn to_i64(&self) -> Option<i64> {
+        Some(i64::from(*self))
+    }
+
+    fn from_i64(x: i64) -> Option<i32> {
+        i32::try_from(x).ok()
+    }
+
+    fn checked_add(&self, other: &i32) -> Option<i32> {
+        i32::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &i32) -> Option<i32> {
+        i32::checked_mul(*self, *other)
+    }
+}
+
+impl Word for i64 {
+    fn to_i64(&self) -> Option<i64> {
+        Some(*self)
+    }
+
+    fn from_i64(x: i64) -> Option<i64> {
+        Some(x)
+    }
+
+    fn checked_add(&self, other: &i64) -> Option<i64> {
+        i64::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &i64) -> Option<i64> {
+        i64::checked_mul(*self, *other)
+    }
+}
+
+impl Word for i128 {
+    fn to_i64(&self) -> Option<i64> {
+        i64::try_from(*self).ok()
+    }
+
+    fn from_i64(x: i64) -> Option<i128> {
+        Some(i128::from(x))
+    }
+
+    fn checked_add(&self, other: &i128) -> Option<i128> {
+        i128::checked_add(*self, *other)
+    }
+
+    fn checked_mul(&self, other: &i128) -> Option<i128> {
+        i128::checked_mul(*self, *other)
+    }
+}
+
+impl Word for BigInt {
+    fn to_i64(&self) -> Option<i64> {
+        ToPrimitive::to_i64(self)
+    }
+
+    fn from_i64(x: i64) -> Option<BigInt> {
+        Some(BigInt::from(x))
+    }
+
+    fn checked_add(&self, other: &BigInt) -> Option<BigInt> {
+        Some(self + other)
+    }
+
+    fn checked_mul(&self, other: &BigInt) -> Option<BigInt> {
+        Some(self * other)
+    }
+}
@@ -0,0 +1,309 @@
+// Generated by intcode2rust. Do not edit.
+
+use std::error::Error;
+use std::io::BufRead;
+use std::thread;
+use crossbeam_channel::{Receiver, Sender, Select};
+
+const PROGRAM: [i64; 91] = [
+    1106, 1, 29, 1106, 1, 38, 1106, 1, 51, 3, 82, 8, 83, 82, 85, 1005,
+    85, 79, 101, 88, 84, 23, 1001, 0, 0, 86, 105, 1, 86, 1002, 83, 2,
+    87, 4, 87, 1105, 1, 57, 1001, 83, 1000, 87, 4, 87, 1001, 83, 0, 53,
+    1105, 1, 57, 1001, 83, 7, 87, 4, 87, 1001, 84, 1, 84, 1008, 84, 3,
+    85, 1006, 85, 72, 1101, 0, 0, 84, 1001, 83, 1, 83, 1105, 1, 11, 4,
+    83, 99, 0, 0, 0, 0, 0, 0, 29, 38, 51,
+];
+
+/// The compiled block that each address of the program belongs to.
+const BLOCK_OF: [usize; 91] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2,
+    2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 3, 3, 3,
+    3, 3, 3, 4, 4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 5, 5,
+    5, 5, 5, 5, 5, 5, 5, 5, 6, 6, 6, 6, 6, 6, 6, 7,
+    7, 7, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE, NONE,
+];
+
+const BLOCKS: usize = 8;
+
+const NONE: usize = usize::MAX;
+
+struct Machine {
+    ip: usize,
+    rb: i64,
+    memory: Vec<i64>,
+    valid: Vec<bool>,
+}
+
+impl Machine {
+    fn new() -> Machine {
+        Machine { ip: 0, rb: 0, memory: PROGRAM.to_vec(), valid: vec![true; BLOCKS] }
+    }
+
+    fn read(&self, address: i64) -> Result<i64, Box<dyn Error>> {
+        if address < 0 {
+            return Err(format!("Negative address: {}", address).into());
+        }
+        Ok(self.memory.get(address as usize).cloned().unwrap_or(0))
+    }
+
+    /// Returns true if the write changed code belonging to a compiled block, which is then no
+    /// longer run natively.
+    fn write(&mut self, address: i64, value: i64) -> Result<bool, Box<dyn Error>> {
+        if address < 0 {
+            return Err(format!("Negative address: {}", address).into());
+        }
+        let address = address as usize;
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        if self.memory[address] == value {
+            return Ok(false);
+        }
+        self.memory[address] = value;
+        match BLOCK_OF.get(address) {
+            Some(&block) if block != NONE && self.valid[block] => {
+                self.valid[block] = false;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn jump(&mut self, target: i64) -> Result<(), Box<dyn Error>> {
+        if target < 0 {
+            return Err(format!("Jump to negative address: {}", target).into());
+        }
+        self.ip = target as usize;
+        Ok(())
+    }
+
+    fn address(&self, offset: usize, mode: i64) -> Result<i64, Box<dyn Error>> {
+        let raw = self.read((self.ip + offset) as i64)?;
+        match mode {
+            0 => Ok(raw),
+            2 => Ok(self.rb + raw),
+            _ => Err(format!("Invalid mode {} for address at ip={}", mode, self.ip).into()),
+        }
+    }
+
+    fn param(&self, offset: usize, mode: i64) -> Result<i64, Box<dyn Error>> {
+        match mode {
+            1 => self.read((self.ip + offset) as i64),
+            _ => self.read(self.address(offset, mode)?),
+        }
+    }
+
+    /// Interprets a single instruction. Returns true once the program has halted.
+    fn step(&mut self, input: &Receiver<i64>, input_request: &Sender<()>, output: &Sender<i64>)
+            -> Result<bool, Box<dyn Error>> {
+        let code = self.read(self.ip as i64)?;
+        let modes = [(code / 100) % 10, (code / 1000) % 10, (code / 10000) % 10];
+        match code % 100 {
+            1 => {
+                let val = i64::checked_add(self.param(1, modes[0])?, self.param(2, modes[1])?)
+                    .ok_or_else(|| format!("arithmetic overflow at ip={}", self.ip))?;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            2 => {
+                let val = i64::checked_mul(self.param(1, modes[0])?, self.param(2, modes[1])?)
+                    .ok_or_else(|| format!("arithmetic overflow at ip={}", self.ip))?;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            3 => {
+                let dest = self.address(1, modes[0])?;
+                input_request.send(())?;
+                let val = input.recv()?;
+                self.write(dest, val)?;
+                self.ip += 2;
+            }
+            4 => {
+                output.send(self.param(1, modes[0])?)?;
+                self.ip += 2;
+            }
+            5 => {
+                if self.param(1, modes[0])? != 0 {
+                    let target = self.param(2, modes[1])?;
+                    self.jump(target)?;
+                } else {
+                    self.ip += 3;
+                }
+            }
+            6 => {
+                if self.param(1, modes[0])? == 0 {
+                    let target = self.param(2, modes[1])?;
+                    self.jump(target)?;
+                } else {
+                    self.ip += 3;
+                }
+            }
+            7 => {
+                let val = (self.param(1, modes[0])? < self.param(2, modes[1])?) as i64;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            8 => {
+                let val = (self.param(1, modes[0])? == self.param(2, modes[1])?) as i64;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            9 => {
+                self.rb += self.param(1, modes[0])?;
+                self.ip += 2;
+            }
+            99 => return Ok(true),
+            _ => return Err(format!("Invalid opcode {} at ip={}", code, self.ip).into()),
+        }
+        Ok(false)
+    }
+}
+
+/// Runs the program to completion with the same channel-based I/O as `aoc::computer::Computer`.
+pub fn run(input: Receiver<i64>, input_request: Sender<()>, output: Sender<i64>) -> Result<(), Box<dyn Error>> {
+    let mut m = Machine::new();
+    loop {
+        match m.ip {
+            0 if m.valid[0] => {
+                // 0: [1106, 1, 29]
+                if 1 == 0 { m.ip = 29; continue; }
+                // 3: [1106, 1, 38]
+                if 1 == 0 { m.ip = 38; continue; }
+                // 6: [1106, 1, 51]
+                if 1 == 0 { m.ip = 51; continue; }
+                // 9: [3, 82]
+                input_request.send(())?;
+                let val = input.recv()?;
+                if m.write(82, val)? { m.ip = 11; continue; }
+                m.ip = 11;
+            }
+            11 if m.valid[1] => {
+                // 11: [8, 83, 82, 85]
+                let val = (m.read(83)? == m.read(82)?) as i64;
+                if m.write(85, val)? { m.ip = 15; continue; }
+                // 15: [1005, 85, 79]
+                if m.read(85)? != 0 { m.ip = 79; continue; }
+                // 18: [101, 88, 84, 23]
+                let val = i64::checked_add(88, m.read(84)?).ok_or("arithmetic overflow at ip=18")?;
+                if m.write(23, val)? { m.ip = 22; continue; }
+                // 22: [1001, 0, 0, 86]
+                let val = i64::checked_add(m.read(0)?, 0).ok_or("arithmetic overflow at ip=22")?;
+                if m.write(86, val)? { m.ip = 26; continue; }
+                // 26: [105, 1, 86]
+                m.jump(m.read(86)?)?;
+                continue;
+            }
+            29 if m.valid[2] => {
+                // 29: [1002, 83, 2, 87]
+                let val = i64::checked_mul(m.read(83)?, 2).ok_or("arithmetic overflow at ip=29")?;
+                if m.write(87, val)? { m.ip = 33; continue; }
+                // 33: [4, 87]
+                output.send(m.read(87)?)?;
+                // 35: [1105, 1, 57]
+                m.ip = 57;
+                continue;
+            }
+            38 if m.valid[3] => {
+                // 38: [1001, 83, 1000, 87]
+                let val = i64::checked_add(m.read(83)?, 1000).ok_or("arithmetic overflow at ip=38")?;
+                if m.write(87, val)? { m.ip = 42; continue; }
+                // 42: [4, 87]
+                output.send(m.read(87)?)?;
+                // 44: [1001, 83, 0, 53]
+                let val = i64::checked_add(m.read(83)?, 0).ok_or("arithmetic overflow at ip=44")?;
+                if m.write(53, val)? { m.ip = 48; continue; }
+                // 48: [1105, 1, 57]
+                m.ip = 57;
+                continue;
+            }
+            51 if m.valid[4] => {
+                // 51: [1001, 83, 7, 87]
+                let val = i64::checked_add(m.read(83)?, 7).ok_or("arithmetic overflow at ip=51")?;
+                if m.write(87, val)? { m.ip = 55; continue; }
+                // 55: [4, 87]
+                output.send(m.read(87)?)?;
+                m.ip = 57;
+            }
+            57 if m.valid[5] => {
+                // 57: [1001, 84, 1, 84]
+                let val = i64::checked_add(m.read(84)?, 1).ok_or("arithmetic overflow at ip=57")?;
+                if m.write(84, val)? { m.ip = 61; continue; }
+                // 61: [1008, 84, 3, 85]
+                let val = (m.read(84)? == 3) as i64;
+                if m.write(85, val)? { m.ip = 65; continue; }
+                // 65: [1006, 85, 72]
+                if m.read(85)? == 0 { m.ip = 72; continue; }
+                // 68: [1101, 0, 0, 84]
+                let val = i64::checked_add(0, 0).ok_or("arithmetic overflow at ip=68")?;
+                if m.write(84, val)? { m.ip = 72; continue; }
+                m.ip = 72;
+            }
+            72 if m.valid[6] => {
+                // 72: [1001, 83, 1, 83]
+                let val = i64::checked_add(m.read(83)?, 1).ok_or("arithmetic overflow at ip=72")?;
+                if m.write(83, val)? { m.ip = 76; continue; }
+                // 76: [1105, 1, 11]
+                m.ip = 11;
+                continue;
+            }
+            79 if m.valid[7] => {
+                // 79: [4, 83]
+                output.send(m.read(83)?)?;
+                // 81: [99]
+                return Ok(());
+            }
+            _ => {
+                if m.step(&input, &input_request, &output)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Reads the program's input from stdin, one value per line, and prints its output.
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let (input_sender, input_receiver) = crossbeam_channel::unbounded();
+    let (output_sender, output_receiver) = crossbeam_channel::unbounded();
+    let (input_request_sender, input_request_receiver) = crossbeam_channel::unbounded();
+    let handle = thread::spawn(move || {
+        run(input_receiver, input_request_sender, output_sender).map_err(|e| e.to_string())
+    });
+
+    let stdin = std::io::stdin();
+    let mut stdin_locked = stdin.lock();
+    loop {
+        let mut sel = Select::new();
+        let in_req_op = sel.recv(&input_request_receiver);
+        let out_op = sel.recv(&output_receiver);
+        let oper = sel.select();
+        match oper.index() {
+            i if i == in_req_op => {
+                if oper.recv(&input_request_receiver).is_err() {
+                    break;
+                }
+                let mut line = String::new();
+                stdin_locked.read_line(&mut line)?;
+                input_sender.send(line.trim().parse()?)?;
+            }
+            i if i == out_op => {
+                match oper.recv(&output_receiver) {
+                    Ok(val) => println!("{}", val),
+                    Err(_) => break,
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    for val in output_receiver.iter() {
+        println!("{}", val);
+    }
+
+    handle.join().unwrap()?;
+    Ok(())
+}
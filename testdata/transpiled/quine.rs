@@ -0,0 +1,227 @@
+// Generated by intcode2rust. Do not edit.
+
+use std::error::Error;
+use std::io::BufRead;
+use std::thread;
+use crossbeam_channel::{Receiver, Sender, Select};
+
+const PROGRAM: [i64; 16] = [
+    109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+];
+
+/// The compiled block that each address of the program belongs to.
+const BLOCK_OF: [usize; 16] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const BLOCKS: usize = 1;
+
+const NONE: usize = usize::MAX;
+
+struct Machine {
+    ip: usize,
+    rb: i64,
+    memory: Vec<i64>,
+    valid: Vec<bool>,
+}
+
+impl Machine {
+    fn new() -> Machine {
+        Machine { ip: 0, rb: 0, memory: PROGRAM.to_vec(), valid: vec![true; BLOCKS] }
+    }
+
+    fn read(&self, address: i64) -> Result<i64, Box<dyn Error>> {
+        if address < 0 {
+            return Err(format!("Negative address: {}", address).into());
+        }
+        Ok(self.memory.get(address as usize).cloned().unwrap_or(0))
+    }
+
+    /// Returns true if the write changed code belonging to a compiled block, which is then no
+    /// longer run natively.
+    fn write(&mut self, address: i64, value: i64) -> Result<bool, Box<dyn Error>> {
+        if address < 0 {
+            return Err(format!("Negative address: {}", address).into());
+        }
+        let address = address as usize;
+        if address >= self.memory.len() {
+            self.memory.resize(address + 1, 0);
+        }
+        if self.memory[address] == value {
+            return Ok(false);
+        }
+        self.memory[address] = value;
+        match BLOCK_OF.get(address) {
+            Some(&block) if block != NONE && self.valid[block] => {
+                self.valid[block] = false;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn jump(&mut self, target: i64) -> Result<(), Box<dyn Error>> {
+        if target < 0 {
+            return Err(format!("Jump to negative address: {}", target).into());
+        }
+        self.ip = target as usize;
+        Ok(())
+    }
+
+    fn address(&self, offset: usize, mode: i64) -> Result<i64, Box<dyn Error>> {
+        let raw = self.read((self.ip + offset) as i64)?;
+        match mode {
+            0 => Ok(raw),
+            2 => Ok(self.rb + raw),
+            _ => Err(format!("Invalid mode {} for address at ip={}", mode, self.ip).into()),
+        }
+    }
+
+    fn param(&self, offset: usize, mode: i64) -> Result<i64, Box<dyn Error>> {
+        match mode {
+            1 => self.read((self.ip + offset) as i64),
+            _ => self.read(self.address(offset, mode)?),
+        }
+    }
+
+    /// Interprets a single instruction. Returns true once the program has halted.
+    fn step(&mut self, input: &Receiver<i64>, input_request: &Sender<()>, output: &Sender<i64>)
+            -> Result<bool, Box<dyn Error>> {
+        let code = self.read(self.ip as i64)?;
+        let modes = [(code / 100) % 10, (code / 1000) % 10, (code / 10000) % 10];
+        match code % 100 {
+            1 => {
+                let val = i64::checked_add(self.param(1, modes[0])?, self.param(2, modes[1])?)
+                    .ok_or_else(|| format!("arithmetic overflow at ip={}", self.ip))?;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            2 => {
+                let val = i64::checked_mul(self.param(1, modes[0])?, self.param(2, modes[1])?)
+                    .ok_or_else(|| format!("arithmetic overflow at ip={}", self.ip))?;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            3 => {
+                let dest = self.address(1, modes[0])?;
+                input_request.send(())?;
+                let val = input.recv()?;
+                self.write(dest, val)?;
+                self.ip += 2;
+            }
+            4 => {
+                output.send(self.param(1, modes[0])?)?;
+                self.ip += 2;
+            }
+            5 => {
+                if self.param(1, modes[0])? != 0 {
+                    let target = self.param(2, modes[1])?;
+                    self.jump(target)?;
+                } else {
+                    self.ip += 3;
+                }
+            }
+            6 => {
+                if self.param(1, modes[0])? == 0 {
+                    let target = self.param(2, modes[1])?;
+                    self.jump(target)?;
+                } else {
+                    self.ip += 3;
+                }
+            }
+            7 => {
+                let val = (self.param(1, modes[0])? < self.param(2, modes[1])?) as i64;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            8 => {
+                let val = (self.param(1, modes[0])? == self.param(2, modes[1])?) as i64;
+                let dest = self.address(3, modes[2])?;
+                self.write(dest, val)?;
+                self.ip += 4;
+            }
+            9 => {
+                self.rb += self.param(1, modes[0])?;
+                self.ip += 2;
+            }
+            99 => return Ok(true),
+            _ => return Err(format!("Invalid opcode {} at ip={}", code, self.ip).into()),
+        }
+        Ok(false)
+    }
+}
+
+/// Runs the program to completion with the same channel-based I/O as `aoc::computer::Computer`.
+pub fn run(input: Receiver<i64>, input_request: Sender<()>, output: Sender<i64>) -> Result<(), Box<dyn Error>> {
+    let mut m = Machine::new();
+    loop {
+        match m.ip {
+            0 if m.valid[0] => {
+                // 0: [109, 1]
+                m.rb += 1;
+                // 2: [204, -1]
+                output.send(m.read(m.rb + -1)?)?;
+                // 4: [1001, 100, 1, 100]
+                let val = i64::checked_add(m.read(100)?, 1).ok_or("arithmetic overflow at ip=4")?;
+                if m.write(100, val)? { m.ip = 8; continue; }
+                // 8: [1008, 100, 16, 101]
+                let val = (m.read(100)? == 16) as i64;
+                if m.write(101, val)? { m.ip = 12; continue; }
+                // 12: [1006, 101, 0]
+                if m.read(101)? == 0 { m.ip = 0; continue; }
+                // 15: [99]
+                return Ok(());
+            }
+            _ => {
+                if m.step(&input, &input_request, &output)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Reads the program's input from stdin, one value per line, and prints its output.
+pub fn main() -> Result<(), Box<dyn Error>> {
+    let (input_sender, input_receiver) = crossbeam_channel::unbounded();
+    let (output_sender, output_receiver) = crossbeam_channel::unbounded();
+    let (input_request_sender, input_request_receiver) = crossbeam_channel::unbounded();
+    let handle = thread::spawn(move || {
+        run(input_receiver, input_request_sender, output_sender).map_err(|e| e.to_string())
+    });
+
+    let stdin = std::io::stdin();
+    let mut stdin_locked = stdin.lock();
+    loop {
+        let mut sel = Select::new();
+        let in_req_op = sel.recv(&input_request_receiver);
+        let out_op = sel.recv(&output_receiver);
+        let oper = sel.select();
+        match oper.index() {
+            i if i == in_req_op => {
+                if oper.recv(&input_request_receiver).is_err() {
+                    break;
+                }
+                let mut line = String::new();
+                stdin_locked.read_line(&mut line)?;
+                input_sender.send(line.trim().parse()?)?;
+            }
+            i if i == out_op => {
+                match oper.recv(&output_receiver) {
+                    Ok(val) => println!("{}", val),
+                    Err(_) => break,
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+    for val in output_receiver.iter() {
+        println!("{}", val);
+    }
+
+    handle.join().unwrap()?;
+    Ok(())
+}